@@ -0,0 +1,261 @@
+use crate::graphics::{Atlas, Texture};
+
+/// Seconds per frame for clips made from an atlas without frame durations
+const DEFAULT_FRAME_DURATION: f32 = 0.1;
+
+/// One texture of a clip and how many seconds it is shown for
+#[derive(Debug, Copy, Clone)]
+pub struct Frame {
+    pub texture: Texture,
+    pub duration: f32,
+}
+
+impl Frame {
+    pub fn new(texture: Texture, duration: f32) -> Frame {
+        Frame { texture, duration }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PlayMode {
+    /// Start over after the last frame
+    Loop,
+    /// Stop on the last frame
+    Once,
+    /// Play forwards, then backwards, and so on
+    PingPong,
+}
+
+#[derive(Debug, Clone)]
+pub struct Clip {
+    pub frames: Vec<Frame>,
+    pub mode: PlayMode,
+    /// Named events fired when the frame at the index is shown, e.g. footsteps
+    pub events: Vec<(usize, String)>,
+}
+
+impl Clip {
+    pub fn new(frames: Vec<Frame>, mode: PlayMode) -> Clip {
+        Clip { frames, mode, events: Vec::new() }
+    }
+
+    /// Tag a frame with an event fired every time the frame is shown
+    pub fn event(mut self, frame: usize, name: &str) -> Self {
+        assert!(frame < self.frames.len(), "Event on a frame the clip doesn't have");
+        self.events.push((frame, name.to_string()));
+        self
+    }
+
+    /// Clip showing every texture for the same time
+    pub fn uniform(textures: &[Texture], duration: f32, mode: PlayMode) -> Clip {
+        Clip::new(textures.iter().map(|&t| Frame::new(t, duration)).collect(), mode)
+    }
+
+    /// Clip of the frames named `name_0.png`, `name_1.png`... in the atlas, see `Atlas::frames`.
+    /// Durations come from the atlas if it has them, otherwise every frame gets a tenth of a
+    /// second. `None` if there are no such frames.
+    pub fn from_atlas(atlas: &Atlas, name: &str, mode: PlayMode) -> Option<Clip> {
+        let textures = atlas.frames(name);
+        if textures.is_empty() { return None; }
+
+        let durations = atlas.frame_durations(name).unwrap_or(&[DEFAULT_FRAME_DURATION]);
+        let frames = textures.iter().enumerate()
+            .map(|(i, &texture)| {
+                let duration = durations.get(i).or(durations.last()).copied().unwrap_or(DEFAULT_FRAME_DURATION);
+                Frame::new(texture, duration)
+            })
+            .collect();
+
+        Some(Clip::new(frames, mode))
+    }
+
+    /// Seconds a single pass over every frame takes
+    pub fn duration(&self) -> f32 {
+        self.frames.iter().map(|f| f.duration).sum()
+    }
+}
+
+/// Something that picks the texture of an entity over time
+pub trait Animator {
+    /// Move the animation on by `dt` seconds, returning the new texture if it changed
+    fn advance(&mut self, dt: f32) -> Option<Texture>;
+
+    /// Texture to show right now
+    fn texture(&self) -> Texture;
+
+    fn finished(&self) -> bool { false }
+
+    /// Names of the events tagged on the frames shown during the last call to `advance`
+    fn frame_events(&self) -> &[String] { &[] }
+}
+
+/// Plays a clip
+#[derive(Debug, Clone)]
+pub struct SpriteAnimation {
+    clip: Clip,
+    /// Multiplier for the frame durations, 2.0 plays twice as fast
+    pub speed: f32,
+    frame: usize,
+    /// Seconds spent on the current frame
+    elapsed: f32,
+    forward: bool,
+    finished: bool,
+    /// Whether the first frame was shown, so its events fire on the first `advance`
+    started: bool,
+    fired: Vec<String>,
+}
+
+impl SpriteAnimation {
+    /// Start playing a clip, which needs at least one frame
+    pub fn new(clip: Clip) -> SpriteAnimation {
+        assert!(!clip.frames.is_empty(), "Clip without frames");
+
+        SpriteAnimation {
+            clip,
+            speed: 1.0,
+            frame: 0,
+            elapsed: 0.0,
+            forward: true,
+            finished: false,
+            started: false,
+            fired: Vec::new(),
+        }
+    }
+
+    /// Switch to another clip from its first frame, keeping the speed
+    pub fn play(&mut self, clip: Clip) {
+        let speed = self.speed;
+        *self = SpriteAnimation::new(clip);
+        self.speed = speed;
+    }
+
+    pub fn restart(&mut self) {
+        self.frame = 0;
+        self.elapsed = 0.0;
+        self.forward = true;
+        self.finished = false;
+        self.started = false;
+    }
+
+    pub fn clip(&self) -> &Clip {
+        &self.clip
+    }
+
+    /// Index of the frame being shown
+    pub fn frame(&self) -> usize {
+        self.frame
+    }
+
+    /// Fire the events of the frame being shown
+    fn enter(&mut self) {
+        let frame = self.frame;
+        self.fired.extend(self.clip.events.iter()
+            .filter(|(f, _)| *f == frame)
+            .map(|(_, name)| name.clone()));
+    }
+
+    /// Move on to the next frame according to the play mode
+    fn step(&mut self) {
+        let last = self.clip.frames.len() - 1;
+
+        match self.clip.mode {
+            PlayMode::Loop => self.frame = if self.frame == last { 0 } else { self.frame + 1 },
+            PlayMode::Once => {
+                if self.frame == last {
+                    self.finished = true;
+                } else {
+                    self.frame += 1;
+                }
+            }
+            PlayMode::PingPong => {
+                if last == 0 { return; }
+
+                if self.forward && self.frame == last || !self.forward && self.frame == 0 {
+                    self.forward = !self.forward;
+                }
+                self.frame = if self.forward { self.frame + 1 } else { self.frame - 1 };
+            }
+        }
+    }
+}
+
+impl Animator for SpriteAnimation {
+    fn advance(&mut self, dt: f32) -> Option<Texture> {
+        self.fired.clear();
+        if self.finished { return None; }
+
+        // The first frame has to reach the sprite too, it may still show another clip
+        let first = !self.started;
+        if first {
+            self.started = true;
+            self.enter();
+        }
+
+        let before = self.frame;
+        self.elapsed += dt * self.speed;
+
+        // A long tick can skip frames, but never more than a whole pass over the clip
+        for _ in 0..self.clip.frames.len() * 2 {
+            let duration = self.clip.frames[self.frame].duration;
+            if self.elapsed < duration || self.finished { break; }
+
+            self.elapsed -= duration;
+            self.step();
+            if !self.finished { self.enter(); }
+        }
+
+        // Time left over after the cap would pile up and make the next ticks skip frames too
+        let duration = self.clip.frames[self.frame].duration;
+        if self.finished || duration <= 0.0 {
+            self.elapsed = 0.0;
+        } else {
+            self.elapsed %= duration;
+        }
+
+        if first || self.frame != before { Some(self.texture()) } else { None }
+    }
+
+    fn texture(&self) -> Texture {
+        self.clip.frames[self.frame].texture
+    }
+
+    fn finished(&self) -> bool {
+        self.finished
+    }
+
+    fn frame_events(&self) -> &[String] {
+        &self.fired
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clip(mode: PlayMode) -> Clip {
+        let textures = [Texture::new(0, 0, 8, 8), Texture::new(8, 0, 8, 8)];
+        Clip::uniform(&textures, 0.1, mode)
+    }
+
+    #[test]
+    fn first_tick_shows_first_frame() {
+        let mut anim = SpriteAnimation::new(clip(PlayMode::Once));
+        assert_eq!(anim.advance(0.01).map(|t| t.x()), Some(0));
+        assert!(anim.advance(0.01).is_none());
+        assert_eq!(anim.advance(0.1).map(|t| t.x()), Some(8));
+    }
+
+    #[test]
+    fn play_and_restart_show_first_frame() {
+        let mut anim = SpriteAnimation::new(clip(PlayMode::Loop));
+        anim.advance(0.15);
+        assert_eq!(anim.frame(), 1);
+
+        anim.restart();
+        assert_eq!(anim.advance(0.01).map(|t| t.x()), Some(0));
+
+        anim.advance(0.1);
+        anim.play(clip(PlayMode::Once));
+        assert_eq!(anim.advance(0.01).map(|t| t.x()), Some(0));
+    }
+}
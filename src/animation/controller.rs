@@ -0,0 +1,241 @@
+use std::collections::HashMap;
+
+use crate::graphics::Texture;
+use super::clip::{Animator, Clip, SpriteAnimation};
+
+/// Value game code hands to a controller to drive its transitions
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Param {
+    Bool(bool),
+    Float(f32),
+    /// Set until a transition that checks it fires
+    Trigger(bool),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    /// The bool parameter has this value, unset parameters are false
+    Bool(String, bool),
+    /// The float parameter is above this value, unset parameters are 0
+    Greater(String, f32),
+    /// The float parameter is below this value
+    Less(String, f32),
+    /// The trigger was set, taking the transition resets it
+    Trigger(String),
+}
+
+/// Change from one state to another once every condition holds
+#[derive(Debug, Clone)]
+pub struct Transition {
+    /// `None` to leave from any state
+    from: Option<String>,
+    to: String,
+    conditions: Vec<Condition>,
+    wait: bool,
+}
+
+impl Transition {
+    pub fn new(from: &str, to: &str) -> Transition {
+        Transition {
+            from: Some(from.to_string()),
+            to: to.to_string(),
+            conditions: Vec::new(),
+            wait: false,
+        }
+    }
+
+    /// Transition from every other state
+    pub fn any(to: &str) -> Transition {
+        Transition {
+            from: None,
+            to: to.to_string(),
+            conditions: Vec::new(),
+            wait: false,
+        }
+    }
+
+    pub fn when(mut self, condition: Condition) -> Self {
+        self.conditions.push(condition);
+        self
+    }
+
+    /// Only leave once the clip of the current state finished
+    pub fn after_finished(mut self) -> Self {
+        self.wait = true;
+        self
+    }
+}
+
+struct State {
+    name: String,
+    animation: SpriteAnimation,
+    priority: u32,
+}
+
+/// State machine picking a clip from parameters set by game code, e.g. idle, run, jump and
+/// attack. The first state added is the one it starts in.
+///
+/// A state can only be interrupted by a state of the same or a higher priority before its clip
+/// finished, so an attack with a higher priority than running plays out even if the player
+/// starts moving.
+pub struct AnimationController {
+    states: Vec<State>,
+    transitions: Vec<Transition>,
+    params: HashMap<String, Param>,
+    current: usize,
+}
+
+impl Default for AnimationController {
+    fn default() -> AnimationController {
+        AnimationController::new()
+    }
+}
+
+impl AnimationController {
+    pub fn new() -> AnimationController {
+        AnimationController {
+            states: Vec::new(),
+            transitions: Vec::new(),
+            params: HashMap::new(),
+            current: 0,
+        }
+    }
+
+    pub fn state(mut self, name: &str, clip: Clip, priority: u32) -> Self {
+        self.states.push(State {
+            name: name.to_string(),
+            animation: SpriteAnimation::new(clip),
+            priority,
+        });
+        self
+    }
+
+    /// Transitions are checked in the order they were added and the first one that can be taken
+    /// is taken
+    pub fn transition(mut self, transition: Transition) -> Self {
+        assert!(self.index(&transition.to).is_some(), "Transition to unknown state {}", transition.to);
+        if let Some(from) = &transition.from {
+            assert!(self.index(from).is_some(), "Transition from unknown state {}", from);
+        }
+
+        self.transitions.push(transition);
+        self
+    }
+
+    /// Name of the state being played
+    pub fn current(&self) -> &str {
+        &self.states[self.current].name
+    }
+
+    pub fn animation(&self) -> &SpriteAnimation {
+        &self.states[self.current].animation
+    }
+
+    pub fn animation_mut(&mut self) -> &mut SpriteAnimation {
+        &mut self.states[self.current].animation
+    }
+
+    pub fn param(&self, name: &str) -> Option<Param> {
+        self.params.get(name).copied()
+    }
+
+    pub fn set_bool(&mut self, name: &str, value: bool) {
+        self.params.insert(name.to_string(), Param::Bool(value));
+    }
+
+    pub fn set_float(&mut self, name: &str, value: f32) {
+        self.params.insert(name.to_string(), Param::Float(value));
+    }
+
+    pub fn trigger(&mut self, name: &str) {
+        self.params.insert(name.to_string(), Param::Trigger(true));
+    }
+
+    pub fn reset_trigger(&mut self, name: &str) {
+        self.params.insert(name.to_string(), Param::Trigger(false));
+    }
+
+    /// Switch to a state right away, ignoring transitions and priorities
+    pub fn play(&mut self, name: &str) {
+        self.current = self.index(name).expect("Unknown animation state");
+        self.states[self.current].animation.restart();
+    }
+
+    fn index(&self, name: &str) -> Option<usize> {
+        self.states.iter().position(|s| s.name == name)
+    }
+
+    fn holds(&self, condition: &Condition) -> bool {
+        match condition {
+            Condition::Bool(name, value) => self.param(name).map_or(!*value, |p| p == Param::Bool(*value)),
+            Condition::Greater(name, value) => self.float(name) > *value,
+            Condition::Less(name, value) => self.float(name) < *value,
+            Condition::Trigger(name) => self.param(name) == Some(Param::Trigger(true)),
+        }
+    }
+
+    fn float(&self, name: &str) -> f32 {
+        match self.param(name) {
+            Some(Param::Float(f)) => f,
+            _ => 0.0,
+        }
+    }
+
+    /// Index of the transition to take, if any
+    fn pick(&self) -> Option<usize> {
+        let state = &self.states[self.current];
+        let finished = state.animation.finished();
+
+        self.transitions.iter().position(|t| {
+            let to = self.index(&t.to).unwrap();
+
+            match &t.from {
+                Some(from) => if *from != state.name { return false; },
+                None => if to == self.current { return false; },
+            }
+
+            (finished || !t.wait && self.states[to].priority >= state.priority)
+                && t.conditions.iter().all(|c| self.holds(c))
+        })
+    }
+
+    fn take(&mut self, transition: usize) {
+        let transition = self.transitions[transition].clone();
+
+        for condition in transition.conditions.iter() {
+            if let Condition::Trigger(name) = condition {
+                self.reset_trigger(name);
+            }
+        }
+
+        self.current = self.index(&transition.to).unwrap();
+        self.states[self.current].animation.restart();
+    }
+}
+
+impl Animator for AnimationController {
+    fn advance(&mut self, dt: f32) -> Option<Texture> {
+        assert!(!self.states.is_empty(), "Animation controller without states");
+
+        let changed = match self.pick() {
+            Some(transition) => { self.take(transition); true }
+            None => false,
+        };
+
+        let advanced = self.animation_mut().advance(dt);
+        if changed { Some(self.texture()) } else { advanced }
+    }
+
+    fn texture(&self) -> Texture {
+        self.animation().texture()
+    }
+
+    /// The clip of the current state finished, the controller still takes transitions
+    fn finished(&self) -> bool {
+        self.animation().finished()
+    }
+
+    fn frame_events(&self) -> &[String] {
+        self.animation().frame_events()
+    }
+}
@@ -0,0 +1,178 @@
+pub mod clip;
+pub mod controller;
+pub mod skeleton;
+
+pub use self::clip::{Frame, PlayMode, Clip, Animator, SpriteAnimation};
+pub use self::controller::{AnimationController, Transition, Condition, Param};
+pub use self::skeleton::{Skeleton, SkeletonClip, Bone, BoneSprite, Keyframe, Transform};
+
+use crate::component::{
+    GraphicsComponent as Graphics,
+    SpriteComponent as Sprite,
+    AnimationComponent as Animation,
+};
+use crate::world::World;
+use crate::query::query;
+use crate::events::EventBus;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AnimationEvent {
+    /// The animation of the entity moved on to its next frame
+    Advanced(usize),
+    /// The animation of the entity reached its end
+    Finished(usize),
+}
+
+/// Named event tagged on a frame, fired when the frame of the entity is shown
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameEvent {
+    pub entity: usize,
+    pub name: String,
+}
+
+pub struct AnimationSystem {
+    events: Vec<AnimationEvent>,
+    frame_events: Vec<FrameEvent>,
+    /// Kept apart so posing skeletons doesn't wipe the events of `animate` or `play`
+    skeleton_events: Vec<AnimationEvent>,
+}
+
+impl AnimationSystem {
+    pub fn new() -> Self {
+        Self {
+            events: Vec::new(),
+            frame_events: Vec::new(),
+            skeleton_events: Vec::new(),
+        }
+    }
+
+    /// Animation events produced by the last call to `animate`
+    pub fn events(&self) -> &[AnimationEvent] {
+        &self.events
+    }
+
+    pub fn drain_events(&mut self) -> std::vec::Drain<'_, AnimationEvent> {
+        self.events.drain(..)
+    }
+
+    /// Frame events fired during the last call to `animate`
+    pub fn frame_events(&self) -> &[FrameEvent] {
+        &self.frame_events
+    }
+
+    pub fn drain_frame_events(&mut self) -> std::vec::Drain<'_, FrameEvent> {
+        self.frame_events.drain(..)
+    }
+
+    /// Skeletons that finished their clip during the last call to `pose`
+    pub fn skeleton_events(&self) -> &[AnimationEvent] {
+        &self.skeleton_events
+    }
+
+    pub fn drain_skeleton_events(&mut self) -> std::vec::Drain<'_, AnimationEvent> {
+        self.skeleton_events.drain(..)
+    }
+
+    /// Send the animation and frame events produced by the last calls to `animate` and `pose`
+    /// to an event bus
+    pub fn publish(&mut self, bus: &mut EventBus) {
+        bus.events_mut::<AnimationEvent>().extend(self.events.drain(..));
+        bus.events_mut::<AnimationEvent>().extend(self.skeleton_events.drain(..));
+        bus.events_mut::<FrameEvent>().extend(self.frame_events.drain(..));
+    }
+
+    fn fire(&mut self, entity: usize, names: &[String]) {
+        self.frame_events.extend(names.iter().map(|name| FrameEvent { entity, name: name.clone() }));
+    }
+
+    pub fn animate<G, A>(
+        &mut self,
+        g: &mut Vec<Option<G>>,
+        a: &mut Vec<Option<A>>,
+    ) where
+        G: Graphics,
+        A: Animation<G>,
+    {
+        self.events.clear();
+        self.frame_events.clear();
+
+        for (i, (graphics, animation)) in query((g, a)) {
+            if animation.finished() { continue; }
+            if animation.ready() {
+                *graphics = animation.next();
+                self.events.push(AnimationEvent::Advanced(i));
+                self.fire(i, animation.frame_events());
+
+                if animation.finished() {
+                    self.events.push(AnimationEvent::Finished(i));
+                }
+            }
+        }
+    }
+
+    /// Advance every animator by `dt` seconds and put the textures they pick on the sprites
+    pub fn play<G, A>(
+        &mut self,
+        g: &mut Vec<Option<G>>,
+        a: &mut Vec<Option<A>>,
+        dt: f32,
+    ) where
+        G: Sprite,
+        A: Animator,
+    {
+        self.events.clear();
+        self.frame_events.clear();
+
+        for (i, (sprite, animator)) in query((g, a)) {
+            // Finished animators are still advanced, a controller can leave a finished state
+            let finished = animator.finished();
+
+            if let Some(texture) = animator.advance(dt) {
+                sprite.set_texture(texture);
+                self.events.push(AnimationEvent::Advanced(i));
+            }
+            self.fire(i, animator.frame_events());
+
+            if !finished && animator.finished() {
+                self.events.push(AnimationEvent::Finished(i));
+            }
+        }
+    }
+
+    /// Advance the clips of every skeleton by `dt` seconds
+    pub fn pose(&mut self, s: &mut Vec<Option<Skeleton>>, dt: f32) {
+        self.skeleton_events.clear();
+
+        for (i, skeleton) in query(s) {
+            let finished = skeleton.finished();
+            skeleton.advance(dt);
+
+            if !finished && skeleton.finished() {
+                self.skeleton_events.push(AnimationEvent::Finished(i));
+            }
+        }
+    }
+
+    /// `play` over the components of a world
+    pub fn play_world<G, A>(&mut self, world: &World, dt: f32)
+    where
+        G: Sprite + 'static,
+        A: Animator + 'static,
+    {
+        self.play(&mut world.borrow_mut::<G>(), &mut world.borrow_mut::<A>(), dt);
+    }
+
+    /// `pose` over the skeletons of a world
+    pub fn pose_world(&mut self, world: &World, dt: f32) {
+        self.pose(&mut world.borrow_mut::<Skeleton>(), dt);
+    }
+
+    /// `animate` over the components of a world
+    pub fn animate_world<G, A>(&mut self, world: &World)
+    where
+        G: Graphics + 'static,
+        A: Animation<G> + 'static,
+    {
+        self.animate(&mut world.borrow_mut::<G>(), &mut world.borrow_mut::<A>());
+    }
+}
@@ -0,0 +1,282 @@
+use crate::graphics::Texture;
+use crate::rect::{Rect, Quad};
+use crate::tween::{Easing, Lerp};
+
+/// Offset, rotation in radians and scale of a bone relative to its parent
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Transform {
+    pub x: f32,
+    pub y: f32,
+    pub rotation: f32,
+    pub scale: (f32, f32),
+}
+
+impl Transform {
+    pub const IDENTITY: Transform = Transform { x: 0.0, y: 0.0, rotation: 0.0, scale: (1.0, 1.0) };
+
+    pub fn new(x: f32, y: f32, rotation: f32) -> Transform {
+        Transform { x, y, rotation, scale: (1.0, 1.0) }
+    }
+
+    /// Point in the space of this transform moved to the space of its parent
+    pub fn apply(&self, point: (f32, f32)) -> (f32, f32) {
+        let (sin, cos) = self.rotation.sin_cos();
+        let (px, py) = (point.0 * self.scale.0, point.1 * self.scale.1);
+
+        (self.x + px * cos - py * sin, self.y + px * sin + py * cos)
+    }
+
+    /// Transform of a child of this transform. Scale isn't skewed by rotation, so a scaled
+    /// parent only keeps its children in place when the scale is uniform.
+    pub fn then(&self, child: &Transform) -> Transform {
+        let (x, y) = self.apply((child.x, child.y));
+
+        Transform {
+            x,
+            y,
+            rotation: self.rotation + child.rotation,
+            scale: (self.scale.0 * child.scale.0, self.scale.1 * child.scale.1),
+        }
+    }
+}
+
+impl Lerp for Transform {
+    fn lerp(self, to: Transform, t: f32) -> Transform {
+        Transform {
+            x: self.x.lerp(to.x, t),
+            y: self.y.lerp(to.y, t),
+            rotation: self.rotation.lerp(to.rotation, t),
+            scale: self.scale.lerp(to.scale, t),
+        }
+    }
+}
+
+/// Texture drawn on a bone
+#[derive(Debug, Copy, Clone)]
+pub struct BoneSprite {
+    pub texture: Texture,
+    /// Where the texture goes in the space of the bone, the bone turns around its origin
+    pub rect: Rect<f32>,
+    /// Sprites with a higher depth are drawn on top
+    pub depth: i32,
+}
+
+#[derive(Debug, Clone)]
+pub struct Bone {
+    pub name: String,
+    /// Parents always come before their children
+    pub parent: Option<usize>,
+    /// Rest pose, used while no clip moves the bone
+    pub local: Transform,
+    pub sprite: Option<BoneSprite>,
+}
+
+/// Local transform of a bone at a point of a clip
+#[derive(Debug, Copy, Clone)]
+pub struct Keyframe {
+    pub time: f32,
+    pub transform: Transform,
+    /// Curve towards the next keyframe
+    pub easing: Easing,
+}
+
+impl Keyframe {
+    pub fn new(time: f32, transform: Transform, easing: Easing) -> Keyframe {
+        Keyframe { time, transform, easing }
+    }
+}
+
+/// Keyframes for some bones of a skeleton, e.g. a swing of the weapon arm
+#[derive(Debug, Clone)]
+pub struct SkeletonClip {
+    pub duration: f32,
+    pub looping: bool,
+    tracks: Vec<(usize, Vec<Keyframe>)>,
+}
+
+impl SkeletonClip {
+    pub fn new(duration: f32, looping: bool) -> SkeletonClip {
+        SkeletonClip { duration, looping, tracks: Vec::new() }
+    }
+
+    /// Keyframes of a bone, by its index in the skeleton. Playing the clip on a skeleton
+    /// without that bone panics.
+    pub fn track(mut self, bone: usize, mut keys: Vec<Keyframe>) -> Self {
+        assert!(!keys.is_empty(), "Track without keyframes");
+        assert!(keys.iter().all(|k| k.time.is_finite()), "Keyframe time has to be finite");
+        keys.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+        self.tracks.push((bone, keys));
+        self
+    }
+
+    fn sample(keys: &[Keyframe], time: f32) -> Transform {
+        let next = keys.iter().position(|k| k.time > time);
+
+        match next {
+            None => keys[keys.len() - 1].transform,
+            Some(0) => keys[0].transform,
+            Some(i) => {
+                let (a, b) = (&keys[i - 1], &keys[i]);
+                let t = (time - a.time) / (b.time - a.time);
+                a.transform.lerp(b.transform, a.easing.apply(t))
+            }
+        }
+    }
+}
+
+/// Hierarchy of bones with a sprite each, for bosses and other sprites built from parts.
+/// Its quads are drawn with `GraphicsSystem::skeleton`.
+#[derive(Debug, Clone)]
+pub struct Skeleton {
+    bones: Vec<Bone>,
+    /// Local transforms of the current pose
+    pose: Vec<Transform>,
+    clip: Option<SkeletonClip>,
+    time: f32,
+    finished: bool,
+}
+
+impl Default for Skeleton {
+    fn default() -> Skeleton {
+        Skeleton::new()
+    }
+}
+
+impl Skeleton {
+    pub fn new() -> Skeleton {
+        Skeleton {
+            bones: Vec::new(),
+            pose: Vec::new(),
+            clip: None,
+            time: 0.0,
+            finished: false,
+        }
+    }
+
+    /// Add a bone, returning its index
+    pub fn add_bone(&mut self, name: &str, parent: Option<usize>, local: Transform, sprite: Option<BoneSprite>) -> usize {
+        if let Some(parent) = parent {
+            assert!(parent < self.bones.len(), "Parent bone has to be added first");
+        }
+
+        self.bones.push(Bone { name: name.to_string(), parent, local, sprite });
+        self.pose.push(local);
+        self.bones.len() - 1
+    }
+
+    pub fn bone(&self, name: &str) -> Option<usize> {
+        self.bones.iter().position(|b| b.name == name)
+    }
+
+    pub fn bones(&self) -> &[Bone] {
+        &self.bones
+    }
+
+    /// Change the rest pose of a bone. Parents can't be changed after adding a bone, they
+    /// have to come before their children.
+    pub fn set_local(&mut self, bone: usize, local: Transform) {
+        self.bones[bone].local = local;
+        self.sample();
+    }
+
+    pub fn set_sprite(&mut self, bone: usize, sprite: Option<BoneSprite>) {
+        self.bones[bone].sprite = sprite;
+    }
+
+    /// Play a clip from its start, bones it has no track for stay in their rest pose
+    pub fn play(&mut self, clip: SkeletonClip) {
+        for (bone, _) in clip.tracks.iter() {
+            assert!(*bone < self.bones.len(),
+                "Clip has a track for bone {} of a skeleton with {} bones", bone, self.bones.len());
+        }
+
+        self.clip = Some(clip);
+        self.time = 0.0;
+        self.finished = false;
+        self.sample();
+    }
+
+    pub fn stop(&mut self) {
+        self.clip = None;
+        self.sample();
+    }
+
+    /// The clip ran to its end without looping
+    pub fn finished(&self) -> bool {
+        self.finished
+    }
+
+    pub fn advance(&mut self, dt: f32) {
+        if let Some(clip) = &self.clip {
+            if self.finished { return; }
+
+            self.time += dt;
+            if self.time >= clip.duration {
+                if clip.looping && clip.duration > 0.0 {
+                    self.time %= clip.duration;
+                } else {
+                    self.time = clip.duration;
+                    self.finished = true;
+                }
+            }
+        }
+
+        self.sample();
+    }
+
+    fn sample(&mut self) {
+        for (pose, bone) in self.pose.iter_mut().zip(self.bones.iter()) {
+            *pose = bone.local;
+        }
+
+        if let Some(clip) = &self.clip {
+            for (bone, keys) in clip.tracks.iter() {
+                self.pose[*bone] = SkeletonClip::sample(keys, self.time);
+            }
+        }
+    }
+
+    /// Transform of every bone relative to the skeleton's origin
+    pub fn world_transforms(&self) -> Vec<Transform> {
+        let mut world: Vec<Transform> = Vec::with_capacity(self.bones.len());
+
+        for (bone, local) in self.bones.iter().zip(self.pose.iter()) {
+            let transform = match bone.parent {
+                Some(parent) => world[parent].then(local),
+                None => *local,
+            };
+            world.push(transform);
+        }
+
+        world
+    }
+
+    /// Texture and corners of every bone sprite, clockwise from the top left and placed at
+    /// `origin`, in drawing order
+    pub fn quads(&self, origin: (f32, f32)) -> Vec<(Texture, Quad)> {
+        let world = self.world_transforms();
+        let root = Transform::new(origin.0, origin.1, 0.0);
+
+        let mut sprites: Vec<(i32, Texture, Quad)> = self.bones.iter()
+            .zip(world.iter())
+            .filter_map(|(bone, transform)| {
+                let sprite = bone.sprite?;
+                let transform = root.then(transform);
+                let r = sprite.rect;
+
+                let corners = [
+                    transform.apply((r.x, r.y)),
+                    transform.apply((r.x + r.w, r.y)),
+                    transform.apply((r.x + r.w, r.y + r.h)),
+                    transform.apply((r.x, r.y + r.h)),
+                ];
+                Some((sprite.depth, sprite.texture, corners))
+            })
+            .collect();
+
+        // Stable, so bones of the same depth keep the order they were added in
+        sprites.sort_by_key(|s| s.0);
+        sprites.into_iter().map(|(_, texture, corners)| (texture, corners)).collect()
+    }
+}
@@ -0,0 +1,168 @@
+use std::time::Instant;
+
+use winit::event::{Event, WindowEvent};
+use winit::event_loop::{ControlFlow, EventLoop};
+
+use crate::graphics::{Atlas, Camera, GraphicsSystem};
+use crate::rect::Rect;
+use crate::events::EventBus;
+
+/// Default length of a fixed update
+const TIMESTEP: f32 = 1.0 / 60.0;
+
+/// Longest frame the fixed stage catches up on, so a stall doesn't turn into a burst of updates
+const MAX_FRAME: f32 = 0.25;
+
+type FixedStage<S> = Box<dyn FnMut(&mut S, f32)>;
+type UpdateStage<S, C> = Box<dyn FnMut(&mut S, &mut GraphicsSystem<C>, f32)>;
+type RenderStage<S, C> = Box<dyn FnMut(&mut S, &mut GraphicsSystem<C>)>;
+type EventHandler<S> = Box<dyn FnMut(&mut S, &WindowEvent<'_>)>;
+type BusAccessor<S> = fn(&mut S) -> &mut EventBus;
+
+/// Owns the window, the graphics system and the game state `S`, and runs the game loop. Every
+/// frame runs, in order:
+///
+/// 1. the fixed stages, zero or more times with a constant `dt` (physics)
+/// 2. the update stages, once with the real frame time (animation, camera follow)
+/// 3. the render stages (transfer), after which the frame is drawn
+pub struct App<C: Camera, S> {
+    event_loop: EventLoop<()>,
+    graphics: GraphicsSystem<C>,
+    state: S,
+    timestep: f32,
+    fixed: Vec<FixedStage<S>>,
+    update: Vec<UpdateStage<S, C>>,
+    render: Vec<RenderStage<S, C>>,
+    events: Vec<EventHandler<S>>,
+    bus: Option<BusAccessor<S>>,
+}
+
+impl<C: Camera + 'static, S: 'static> App<C, S> {
+    pub fn new(
+        atlas: Atlas,
+        cam_rect: Rect<f32>,
+        player_box: Rect<u32>,
+        zoom: f32,
+        state: S
+    ) -> App<C, S> {
+        let event_loop = EventLoop::new();
+        let graphics = GraphicsSystem::new(&event_loop, atlas, cam_rect, player_box, zoom);
+
+        App {
+            event_loop,
+            graphics,
+            state,
+            timestep: TIMESTEP,
+            fixed: Vec::new(),
+            update: Vec::new(),
+            render: Vec::new(),
+            events: Vec::new(),
+            bus: None,
+        }
+    }
+
+    pub fn graphics(&mut self) -> &mut GraphicsSystem<C> {
+        &mut self.graphics
+    }
+
+    pub fn state(&mut self) -> &mut S {
+        &mut self.state
+    }
+
+    /// Length in seconds of every fixed update. Physics run there should use the same step,
+    /// see `PhysicsSystem::set_timestep`.
+    pub fn timestep(mut self, dt: f32) -> Self {
+        self.timestep = dt;
+        self
+    }
+
+    pub fn fixed_update<F: FnMut(&mut S, f32) + 'static>(mut self, stage: F) -> Self {
+        self.fixed.push(Box::new(stage));
+        self
+    }
+
+    pub fn update<F: FnMut(&mut S, &mut GraphicsSystem<C>, f32) + 'static>(mut self, stage: F) -> Self {
+        self.update.push(Box::new(stage));
+        self
+    }
+
+    pub fn render<F: FnMut(&mut S, &mut GraphicsSystem<C>) + 'static>(mut self, stage: F) -> Self {
+        self.render.push(Box::new(stage));
+        self
+    }
+
+    /// Called for every window event, e.g. for input
+    pub fn on_event<F: FnMut(&mut S, &WindowEvent<'_>) + 'static>(mut self, handler: F) -> Self {
+        self.events.push(Box::new(handler));
+        self
+    }
+
+    /// Event bus kept in the state, its queues move on to a new frame before each frame's
+    /// stages run
+    pub fn event_bus(mut self, bus: BusAccessor<S>) -> Self {
+        self.bus = Some(bus);
+        self
+    }
+
+    pub fn run(self) -> ! {
+        let App {
+            event_loop,
+            mut graphics,
+            mut state,
+            timestep,
+            mut fixed,
+            mut update,
+            mut render,
+            mut events,
+            bus,
+        } = self;
+
+        let mut last_frame = Instant::now();
+        let mut accumulator = 0.0;
+
+        event_loop.run(move |ev, _, control_flow| {
+            *control_flow = ControlFlow::Poll;
+
+            match ev {
+                Event::WindowEvent { event, .. } => {
+                    match event {
+                        WindowEvent::CloseRequested => *control_flow = ControlFlow::Exit,
+                        WindowEvent::Resized(_) => graphics.handle_resize(),
+                        _ => {}
+                    }
+
+                    for handler in events.iter_mut() {
+                        handler(&mut state, &event);
+                    }
+                }
+                Event::MainEventsCleared => {
+                    let dt = last_frame.elapsed().as_secs_f32().min(MAX_FRAME);
+                    last_frame = Instant::now();
+
+                    if let Some(bus) = bus {
+                        bus(&mut state).update();
+                    }
+
+                    accumulator += dt;
+                    while accumulator >= timestep {
+                        for stage in fixed.iter_mut() {
+                            stage(&mut state, timestep);
+                        }
+                        accumulator -= timestep;
+                    }
+
+                    for stage in update.iter_mut() {
+                        stage(&mut state, &mut graphics, dt);
+                    }
+
+                    for stage in render.iter_mut() {
+                        stage(&mut state, &mut graphics);
+                    }
+
+                    graphics.draw();
+                }
+                _ => {}
+            }
+        })
+    }
+}
@@ -1,5 +1,6 @@
 use crate::rect::Rect;
 use crate::graphics::Texture;
+use crate::physics::{BodyType, Shape};
 
 pub trait PositionComponent {
     fn x(&self) -> f32;
@@ -21,15 +22,78 @@ pub trait PhysicsComponent {
     fn hitbox(&self) -> Rect<f32>;
     fn set_x_collision(&mut self, with: Option<Rect<f32>>);
     fn set_y_collision(&mut self, with: Option<Rect<f32>>);
+
+    /// Bits of the collision categories this hitbox belongs to
+    fn category(&self) -> u32 { 1 }
+
+    /// Bits of the categories this hitbox collides with
+    fn mask(&self) -> u32 { u32::MAX }
+
+    /// Sensors never block movement, they only produce collision events
+    fn is_sensor(&self) -> bool { false }
+
+    fn body_type(&self) -> BodyType { BodyType::Dynamic }
+
+    /// Exact shape used against other entities. The hitbox should stay its bounds, since it is
+    /// still what gets tested against collision maps.
+    fn shape(&self) -> Shape { Shape::Aabb(self.hitbox()) }
+
+    /// Fraction of the speed kept when bouncing off something, 0 stops dead
+    fn restitution(&self) -> f32 { 0.0 }
+
+    /// Bounce along the normal of the contact instead of only reversing the blocked axis,
+    /// so round shapes glance off corners and each other
+    fn reflect(&self) -> bool { false }
+}
+
+/// Optional body properties integrated by the physics system
+pub trait BodyComponent {
+    /// Mass of the body, bodies with no positive mass are never pushed out of overlaps
+    fn mass(&self) -> f32 { 1.0 }
+
+    /// Multiplier for the gravity of the physics system
+    fn gravity_scale(&self) -> f32 { 1.0 }
+
+    /// Fraction of the velocity lost every second
+    fn linear_damping(&self) -> f32 { 0.0 }
+
+    /// Horizontal deceleration while on the ground and not accelerating
+    fn friction(&self) -> f32 { 0.0 }
+
+    /// Maximum horizontal speed
+    fn max_speed(&self) -> f32 { f32::INFINITY }
+
+    /// Maximum falling speed
+    fn terminal_velocity(&self) -> f32 { f32::INFINITY }
+
+    /// Acceleration the body applies itself, e.g. from player input
+    fn acceleration(&self) -> (f32, f32) { (0.0, 0.0) }
+
+    fn on_ground(&self) -> bool;
+    fn on_wall(&self) -> bool;
+    fn set_on_ground(&mut self, on_ground: bool);
+    fn set_on_wall(&mut self, on_wall: bool);
 }
 
 pub trait GraphicsComponent {
     fn texture(&self) -> Texture;
     fn renderbox(&self) -> Rect<f32>;
+
+    /// Color the texture is multiplied with
+    fn tint(&self) -> [f32; 4] { [1.0; 4] }
+}
+
+/// Graphics whose texture and tint can be swapped out, e.g. by a `SpriteAnimation` or a tween
+pub trait SpriteComponent: GraphicsComponent {
+    fn set_texture(&mut self, texture: Texture);
+    fn set_tint(&mut self, tint: [f32; 4]);
 }
 
 pub trait AnimationComponent<G: GraphicsComponent> {
     fn next(&mut self) -> G;
     fn ready(&self) -> bool;
     fn finished(&self) -> bool { false }
+
+    /// Names of the events tagged on the frame the last call to `next` moved to
+    fn frame_events(&self) -> &[String] { &[] }
 }
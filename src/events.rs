@@ -0,0 +1,149 @@
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+/// Double buffered queue of events of one type. Events can be read during the frame they were
+/// sent and the one after, then they are dropped by `update`.
+#[derive(Debug)]
+pub struct Events<T> {
+    previous: Vec<T>,
+    current: Vec<T>,
+    /// Id of the first event in `previous`, ids count every event ever sent
+    start: usize,
+}
+
+/// Position of one reader in an `Events` queue. Every reader sees each event once.
+#[derive(Debug)]
+pub struct EventReader<T> {
+    cursor: usize,
+    _events: PhantomData<fn() -> T>,
+}
+
+impl<T> Default for Events<T> {
+    fn default() -> Events<T> {
+        Events::new()
+    }
+}
+
+impl<T> Events<T> {
+    pub fn new() -> Events<T> {
+        Events {
+            previous: Vec::new(),
+            current: Vec::new(),
+            start: 0,
+        }
+    }
+
+    pub fn send(&mut self, event: T) {
+        self.current.push(event);
+    }
+
+    /// Number of events that can still be read
+    pub fn len(&self) -> usize {
+        self.previous.len() + self.current.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drop the events of the last frame and start a new one
+    pub fn update(&mut self) {
+        self.start += self.previous.len();
+        self.previous = std::mem::take(&mut self.current);
+    }
+
+    /// A reader that will see every event still kept
+    pub fn reader(&self) -> EventReader<T> {
+        EventReader { cursor: self.start, _events: PhantomData }
+    }
+
+    /// Events the reader hasn't seen yet, oldest first
+    pub fn read<'a>(&'a self, reader: &mut EventReader<T>) -> impl Iterator<Item = &'a T> {
+        // Readers that fell behind a whole frame miss the dropped events
+        let skip = reader.cursor.saturating_sub(self.start);
+        reader.cursor = self.start + self.len();
+
+        self.previous.iter().chain(self.current.iter()).skip(skip)
+    }
+}
+
+impl<T> Extend<T> for Events<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.current.extend(iter);
+    }
+}
+
+/// Event queue with its type erased
+trait Queue {
+    fn update(&mut self);
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: 'static> Queue for Events<T> {
+    fn update(&mut self) {
+        Events::update(self);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// An `Events` queue for every event type, so systems can talk without knowing each other
+pub struct EventBus {
+    queues: HashMap<TypeId, Box<dyn Queue>>,
+}
+
+impl Default for EventBus {
+    fn default() -> EventBus {
+        EventBus::new()
+    }
+}
+
+impl EventBus {
+    pub fn new() -> EventBus {
+        EventBus {
+            queues: HashMap::new(),
+        }
+    }
+
+    pub fn send<T: 'static>(&mut self, event: T) {
+        self.events_mut::<T>().send(event);
+    }
+
+    /// Queue of an event type, `None` if nothing was ever sent or read with it
+    pub fn events<T: 'static>(&self) -> Option<&Events<T>> {
+        self.queues.get(&TypeId::of::<T>())
+            .map(|q| q.as_any().downcast_ref::<Events<T>>().unwrap())
+    }
+
+    pub fn events_mut<T: 'static>(&mut self) -> &mut Events<T> {
+        self.queues.entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(Events::<T>::new()))
+            .as_any_mut()
+            .downcast_mut::<Events<T>>()
+            .unwrap()
+    }
+
+    /// A reader for an event type, seeing every event still kept
+    pub fn reader<T: 'static>(&mut self) -> EventReader<T> {
+        self.events_mut::<T>().reader()
+    }
+
+    pub fn read<'a, T: 'static>(&'a self, reader: &mut EventReader<T>) -> impl Iterator<Item = &'a T> {
+        self.events::<T>().map(|events| events.read(reader)).into_iter().flatten()
+    }
+
+    /// Start a new frame for every queue, called once per frame by `App`
+    pub fn update(&mut self) {
+        for queue in self.queues.values_mut() {
+            queue.update();
+        }
+    }
+}
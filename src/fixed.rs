@@ -0,0 +1,121 @@
+use std::ops::{Add, Sub, Mul, Div, Neg};
+
+use serde::{Serialize, Deserialize};
+
+use crate::rect::{Rect, IntoF32};
+
+const FRAC_BITS: u32 = 16;
+const ONE: i64 = 1 << FRAC_BITS;
+
+/// Largest magnitude a float can have and still convert exactly, 2^47. Floats past 2^23 are
+/// whole numbers, so every float below this fits.
+const LIMIT: f32 = (1u64 << (63 - FRAC_BITS)) as f32;
+
+/// 48.16 fixed point number. Arithmetic on it gives the same result on every platform, which
+/// floats only promise for a single build. Results out of range saturate at `MIN` and `MAX`
+/// instead of wrapping, and dividing by zero saturates as well.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Fixed(i64);
+
+impl Fixed {
+    pub const ZERO: Fixed = Fixed(0);
+    pub const ONE: Fixed = Fixed(ONE);
+    pub const MIN: Fixed = Fixed(i64::MIN);
+    pub const MAX: Fixed = Fixed(i64::MAX);
+
+    pub fn from_raw(raw: i64) -> Fixed {
+        Fixed(raw)
+    }
+
+    pub fn raw(self) -> i64 {
+        self.0
+    }
+
+    pub fn abs(self) -> Fixed {
+        Fixed(self.0.saturating_abs())
+    }
+
+    /// Round a float to the closest value a `Fixed` can hold. Floats out of range, infinite
+    /// or NaN are returned as they are.
+    pub fn snap(x: f32) -> f32 {
+        if x.abs() < LIMIT { Fixed::from(x).to_f32() } else { x }
+    }
+
+    fn saturate(raw: i128) -> Fixed {
+        Fixed(raw.clamp(i64::MIN as i128, i64::MAX as i128) as i64)
+    }
+}
+
+/// Floats out of range saturate and NaN becomes zero
+impl From<f32> for Fixed {
+    fn from(x: f32) -> Fixed {
+        Fixed((x * ONE as f32).round() as i64)
+    }
+}
+
+impl From<i32> for Fixed {
+    fn from(x: i32) -> Fixed {
+        Fixed((x as i64) << FRAC_BITS)
+    }
+}
+
+impl IntoF32 for Fixed {
+    fn to_f32(self) -> f32 { self.0 as f32 / ONE as f32 }
+}
+
+impl Add for Fixed {
+    type Output = Fixed;
+    fn add(self, other: Fixed) -> Fixed { Fixed(self.0.saturating_add(other.0)) }
+}
+
+impl Sub for Fixed {
+    type Output = Fixed;
+    fn sub(self, other: Fixed) -> Fixed { Fixed(self.0.saturating_sub(other.0)) }
+}
+
+impl Mul for Fixed {
+    type Output = Fixed;
+    fn mul(self, other: Fixed) -> Fixed {
+        Fixed::saturate((self.0 as i128 * other.0 as i128) >> FRAC_BITS)
+    }
+}
+
+impl Div for Fixed {
+    type Output = Fixed;
+    fn div(self, other: Fixed) -> Fixed {
+        if other.0 == 0 {
+            return match self.0.signum() {
+                1 => Fixed::MAX,
+                -1 => Fixed::MIN,
+                _ => Fixed::ZERO,
+            };
+        }
+
+        Fixed::saturate(((self.0 as i128) << FRAC_BITS) / other.0 as i128)
+    }
+}
+
+impl Neg for Fixed {
+    type Output = Fixed;
+    fn neg(self) -> Fixed { Fixed(self.0.saturating_neg()) }
+}
+
+impl Rect<Fixed> {
+    /// Check if this rectangle overlaps another one, touching edges do not count
+    pub fn has_intersection(&self, other: &Rect<Fixed>) -> bool {
+        self.x < other.x + other.w &&
+            other.x < self.x + self.w &&
+            self.y < other.y + other.h &&
+            other.y < self.y + self.h
+    }
+
+    pub fn to_f32(&self) -> Rect<f32> {
+        Rect::new(self.x.to_f32(), self.y.to_f32(), self.w.to_f32(), self.h.to_f32())
+    }
+}
+
+impl Rect<f32> {
+    pub fn to_fixed(&self) -> Rect<Fixed> {
+        Rect::new(self.x.into(), self.y.into(), self.w.into(), self.h.into())
+    }
+}
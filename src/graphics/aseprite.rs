@@ -0,0 +1,154 @@
+use serde::de::{Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::Deserialize;
+use std::{collections::HashMap, fmt, fs::File, io::BufReader, path::{Path, PathBuf}};
+
+use crate::animation::{Clip, Frame, PlayMode};
+use super::atlas::Texture;
+
+#[derive(Debug, Deserialize)]
+struct AsepriteRect {
+    x: u32,
+    y: u32,
+    w: u32,
+    h: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct AsepriteSize {
+    w: u32,
+    h: u32,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AsepriteFrame {
+    #[serde(default)]
+    filename: String,
+    frame: AsepriteRect,
+    /// Transparent borders were cut off, `frame` is only the part inside `sprite_source_size`
+    #[serde(default)]
+    trimmed: bool,
+    sprite_source_size: Option<AsepriteRect>,
+    source_size: Option<AsepriteSize>,
+    /// Milliseconds
+    duration: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct AsepriteTag {
+    name: String,
+    from: usize,
+    to: usize,
+    direction: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AsepriteMeta {
+    image: String,
+    size: AsepriteSize,
+    #[serde(default)]
+    frame_tags: Vec<AsepriteTag>,
+}
+
+/// Frames of the export in sheet order, Aseprite writes them either as an array or as an
+/// object keyed by file name
+#[derive(Debug)]
+struct AsepriteFrames(Vec<AsepriteFrame>);
+
+#[derive(Debug, Deserialize)]
+struct AsepriteData {
+    frames: AsepriteFrames,
+    meta: AsepriteMeta,
+}
+
+impl<'de> Deserialize<'de> for AsepriteFrames {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct FramesVisitor;
+
+        impl<'de> Visitor<'de> for FramesVisitor {
+            type Value = AsepriteFrames;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an array or a map of frames")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<AsepriteFrames, A::Error> {
+                let mut frames = Vec::new();
+                while let Some(frame) = seq.next_element()? {
+                    frames.push(frame);
+                }
+                Ok(AsepriteFrames(frames))
+            }
+
+            // Keeps the order of the file, which is the frame order
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<AsepriteFrames, A::Error> {
+                let mut frames = Vec::new();
+                while let Some((filename, mut frame)) = map.next_entry::<String, AsepriteFrame>()? {
+                    frame.filename = filename;
+                    frames.push(frame);
+                }
+                Ok(AsepriteFrames(frames))
+            }
+        }
+
+        deserializer.deserialize_any(FramesVisitor)
+    }
+}
+
+/// Sprite sheet exported by Aseprite, converted to what an `Atlas` keeps
+pub(crate) struct AsepriteSheet {
+    pub records: HashMap<String, Texture>,
+    pub clips: HashMap<String, Clip>,
+    /// The sheet png, next to the json
+    pub image: PathBuf,
+    pub width: u32,
+    pub height: u32,
+}
+
+pub(crate) fn load(path: &str) -> AsepriteSheet {
+    let reader = BufReader::new(File::open(path).unwrap());
+    let data: AsepriteData = serde_json::from_reader(reader).unwrap();
+
+    let frames: Vec<Frame> = data.frames.0.iter()
+        .map(|f| {
+            let mut texture = Texture::new(f.frame.x, f.frame.y, f.frame.w, f.frame.h);
+            if let (true, Some(offset), Some(size)) = (f.trimmed, &f.sprite_source_size, &f.source_size) {
+                texture = texture.trimmed((offset.x, offset.y), (size.w, size.h));
+            }
+            Frame::new(texture, f.duration as f32 / 1000.0)
+        })
+        .collect();
+
+    let records = data.frames.0.iter()
+        .zip(frames.iter())
+        .map(|(f, frame)| (f.filename.clone(), frame.texture))
+        .collect();
+
+    let mut clips = HashMap::new();
+    for tag in data.meta.frame_tags {
+        assert!(tag.from <= tag.to && tag.to < frames.len(), "Tag {} out of the frames", tag.name);
+
+        let mut tagged = frames[tag.from..=tag.to].to_vec();
+        let mode = match tag.direction.as_str() {
+            "forward" => PlayMode::Loop,
+            "reverse" => { tagged.reverse(); PlayMode::Loop }
+            "pingpong" => PlayMode::PingPong,
+            "pingpong_reverse" => { tagged.reverse(); PlayMode::PingPong }
+            // Directions of newer Aseprite versions play forward
+            _ => PlayMode::Loop,
+        };
+
+        clips.insert(tag.name, Clip::new(tagged, mode));
+    }
+
+    let image = Path::new(path).parent().unwrap_or(Path::new("")).join(&data.meta.image);
+
+    AsepriteSheet {
+        records,
+        clips,
+        image,
+        width: data.meta.size.w,
+        height: data.meta.size.h,
+    }
+}
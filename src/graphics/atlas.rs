@@ -1,9 +1,13 @@
 use png::OutputInfo;
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, fs::File, io::{BufReader, Read}};
+use std::{cmp::Ordering, collections::HashMap, fs::File, io::{BufReader, Read}, path::PathBuf};
 use zip::ZipArchive;
 use bincode::deserialize;
 
+use crate::animation::Clip;
+use crate::rect::Rect;
+use super::aseprite;
+
 #[derive(Debug, Deserialize)]
 struct AtlasRecord {
     x: u32,
@@ -20,6 +24,14 @@ struct AtlasData {
     height: u32,
 }
 
+/// Frame durations of one animation, read from the optional `animations.data` in the atlas
+#[derive(Debug, Deserialize)]
+struct AnimationRecord {
+    name: String,
+    /// Seconds per frame, a single value applies to every frame
+    durations: Vec<f32>,
+}
+
 impl AtlasRecord {
     fn texture(&self) -> Texture {
         Texture::new(self.x, self.y, self.width, self.height)
@@ -32,7 +44,14 @@ pub struct Texture{
     ne: [u32; 2],
     se: [u32; 2],
     sw: [u32; 2],
-    flipped: bool
+    flipped: bool,
+    /// Part of the untrimmed image the texture covers, as `[x, y, w, h]` fractions of it
+    #[serde(default = "untrimmed")]
+    trim: [f32; 4],
+}
+
+fn untrimmed() -> [f32; 4] {
+    [0.0, 0.0, 1.0, 1.0]
 }
 
 impl Texture {
@@ -42,10 +61,32 @@ impl Texture {
             ne: [x + width, y],
             se: [x + width, y + height],
             sw: [x, y + height],
-            flipped: false
+            flipped: false,
+            trim: untrimmed(),
         }
     }
 
+    /// Texture of an image whose transparent border was cut off when packing. `offset` is
+    /// where the kept part was in the image of `size`, sprites drawing the texture keep their
+    /// renderbox the size of the whole image.
+    pub fn trimmed(mut self, offset: (u32, u32), size: (u32, u32)) -> Self {
+        let (w, h) = (size.0 as f32, size.1 as f32);
+        self.trim = [
+            offset.0 as f32 / w,
+            offset.1 as f32 / h,
+            self.width() as f32 / w,
+            self.height() as f32 / h,
+        ];
+        self
+    }
+
+    /// Part of a quad for the untrimmed image the texture covers, as fractions of its sides,
+    /// mirrored when flipped
+    pub fn trim(&self) -> Rect<f32> {
+        let [x, y, w, h] = self.trim;
+        Rect::new(if self.flipped { 1.0 - x - w } else { x }, y, w, h)
+    }
+
     pub fn set_flipped(&mut self, flipped: bool) {
         self.flipped = flipped;
     }
@@ -73,9 +114,18 @@ impl Texture {
 }
 
 
+/// Where the atlas image is read from
+enum AtlasImage {
+    /// `atlas.png` in the atlas zip
+    Zip(String),
+    Png(PathBuf),
+}
+
 pub struct Atlas {
     records: HashMap<String, Texture>,
-    path: String,
+    durations: HashMap<String, Vec<f32>>,
+    clips: HashMap<String, Clip>,
+    image: AtlasImage,
     pub width: u32,
     pub height: u32
 }
@@ -97,22 +147,61 @@ impl Atlas {
         for r in data.records {
             record_map.insert(r.name.clone(), r.texture());
         }
+        drop(metadata);
+
+        // Frame durations are optional
+        let mut durations = HashMap::new();
+        if let Ok(mut file) = zip.by_name("animations.data") {
+            let mut buf = Vec::new();
+            file.read_to_end(&mut buf).unwrap();
+
+            let records: Vec<AnimationRecord> = deserialize(&buf).unwrap();
+            for r in records {
+                durations.insert(r.name, r.durations);
+            }
+        }
 
         Atlas {
-            path: path.to_string(),
+            image: AtlasImage::Zip(path.to_string()),
             records: record_map,
+            durations,
+            clips: HashMap::new(),
             width: data.width,
             height: data.height
         }
     }
 
-    pub fn image_data(&self) -> (OutputInfo, Vec<u8>) {
-        let reader = BufReader::new(File::open(self.path.clone()).unwrap());
-        let mut zip = ZipArchive::new(reader).unwrap();
+    /// Atlas from a sprite sheet exported by Aseprite as a png and a json file, e.g.
+    /// `aseprite -b player.aseprite --sheet player.png --data player.json --list-tags`.
+    /// Textures are named after the frame file names, and every tag becomes a clip.
+    pub fn from_aseprite(path: &str) -> Atlas {
+        let sheet = aseprite::load(path);
 
-        let mut file = zip.by_name("atlas.png").unwrap();
+        Atlas {
+            image: AtlasImage::Png(sheet.image),
+            records: sheet.records,
+            durations: HashMap::new(),
+            clips: sheet.clips,
+            width: sheet.width,
+            height: sheet.height
+        }
+    }
+
+    pub fn image_data(&self) -> (OutputInfo, Vec<u8>) {
         let mut buf = Vec::new();
-        file.read_to_end(&mut buf).unwrap();
+
+        match &self.image {
+            AtlasImage::Zip(path) => {
+                let reader = BufReader::new(File::open(path).unwrap());
+                let mut zip = ZipArchive::new(reader).unwrap();
+
+                let mut file = zip.by_name("atlas.png").unwrap();
+                file.read_to_end(&mut buf).unwrap();
+            }
+            AtlasImage::Png(path) => {
+                File::open(path).unwrap().read_to_end(&mut buf).unwrap();
+            }
+        }
 
         let decoder = png::Decoder::new(std::io::Cursor::new(buf));
         let mut reader = decoder.read_info().unwrap();
@@ -126,4 +215,116 @@ impl Atlas {
     pub fn get(&self, key: &str) -> Option<Texture> {
         self.records.get(key).map(|t| t.clone())
     }
+
+    /// Textures whose names start with `prefix`, in natural order so `run_2` comes before
+    /// `run_10`
+    pub fn prefixed(&self, prefix: &str) -> Vec<(&str, Texture)> {
+        self.filtered(|name| name.starts_with(prefix))
+    }
+
+    /// Textures whose names match a pattern where `*` stands for any number of characters,
+    /// e.g. `player/*_0.png`, in natural order
+    pub fn matching(&self, pattern: &str) -> Vec<(&str, Texture)> {
+        self.filtered(|name| wildcard_match(pattern, name))
+    }
+
+    /// Frames of the animation `name`, the textures named `name_0.png`, `name_1.png` and so on,
+    /// ordered by their number
+    pub fn frames(&self, name: &str) -> Vec<Texture> {
+        let prefix = format!("{}_", name);
+
+        self.filtered(|key| {
+            key.strip_prefix(&prefix)
+                .map(|rest| rest.split('.').next().unwrap())
+                .map_or(false, |number| !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit()))
+        })
+            .into_iter()
+            .map(|(_, texture)| texture)
+            .collect()
+    }
+
+    /// Clip of an Aseprite tag, looping or ping-ponging like the tag. Reverse tags play their
+    /// frames backwards.
+    pub fn clip(&self, tag: &str) -> Option<Clip> {
+        self.clips.get(tag).cloned()
+    }
+
+    /// Frame durations of the animation `name` from `animations.data`, if the atlas has them
+    pub fn frame_durations(&self, name: &str) -> Option<&[f32]> {
+        self.durations.get(name).map(|d| d.as_slice())
+    }
+
+    fn filtered<F: Fn(&str) -> bool>(&self, keep: F) -> Vec<(&str, Texture)> {
+        let mut found: Vec<(&str, Texture)> = self.records.iter()
+            .filter(|(name, _)| keep(name))
+            .map(|(name, texture)| (name.as_str(), *texture))
+            .collect();
+
+        found.sort_by(|a, b| natural_cmp(a.0, b.0));
+        found
+    }
+}
+
+/// Compare strings with runs of digits compared by their value
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a = a.chars().peekable();
+    let mut b = b.chars().peekable();
+
+    loop {
+        match (a.peek().copied(), b.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let mut number = |chars: &mut std::iter::Peekable<std::str::Chars>| {
+                    let mut n: u64 = 0;
+                    while let Some(d) = chars.peek().and_then(|c| c.to_digit(10)) {
+                        n = n.saturating_mul(10).saturating_add(d as u64);
+                        chars.next();
+                    }
+                    n
+                };
+
+                match number(&mut a).cmp(&number(&mut b)) {
+                    Ordering::Equal => {}
+                    order => return order,
+                }
+            }
+            (Some(x), Some(y)) => {
+                match x.cmp(&y) {
+                    Ordering::Equal => { a.next(); b.next(); }
+                    order => return order,
+                }
+            }
+        }
+    }
+}
+
+/// Match a name against a pattern where `*` stands for any number of characters
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+
+    let (mut p, mut n) = (0, 0);
+    // Position of the last star and where in the name it started matching
+    let mut star: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, n));
+            p += 1;
+        } else if p < pattern.len() && pattern[p] == name[n] {
+            p += 1;
+            n += 1;
+        } else if let Some((sp, sn)) = star {
+            // Let the star swallow one more character
+            p = sp + 1;
+            n = sn + 1;
+            star = Some((sp, sn + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
 }
@@ -8,12 +8,169 @@ pub trait Camera {
     fn viewport(&self) -> ([u32; 2], [u32; 2]);
     fn pan_to(&mut self, rect: &Rect<f32>);
     fn rect(&self) -> &Rect<f32>;
+
+    /// Put the top left of the view at a point, ignoring the player box but staying in bounds.
+    /// Cameras that only move through `pan_to` ignore this.
+    fn set_position(&mut self, _x: f32, _y: f32) {}
+
+    /// Change how much of the world the view shows. The pixel scale and viewport follow, so
+    /// the camera may round the size to what the window fits. Cameras of a fixed size ignore
+    /// this.
+    fn set_size(&mut self, _w: f32, _h: f32) {}
+
+    /// Move towards a target moving at `velocity` according to the camera's `Follow` settings,
+    /// an eased alternative to `pan_to`. Cameras without `Follow` settings just `pan_to` it.
+    fn follow(&mut self, rect: &Rect<f32>, _velocity: (f32, f32), _dt: f32) {
+        self.pan_to(rect);
+    }
+
+    /// Keep the view inside the level, or centered on it when the level is smaller than the
+    /// view. `None` lets the camera go anywhere. Cameras without bounds ignore this.
+    fn set_bounds(&mut self, _bounds: Option<Rect<f32>>) {}
+}
+
+/// Move a camera rect back inside the bounds, centering it on axes where it doesn't fit
+fn clamp_to_bounds(rect: &mut Rect<f32>, bounds: &Option<Rect<f32>>) {
+    let bounds = match bounds {
+        Some(bounds) => bounds,
+        None => return,
+    };
+
+    if rect.w >= bounds.w {
+        rect.x = bounds.x + (bounds.w - rect.w) / 2.0;
+    } else {
+        rect.x = rect.x.clamp(bounds.x, bounds.x + bounds.w - rect.w);
+    }
+
+    if rect.h >= bounds.h {
+        rect.y = bounds.y + (bounds.h - rect.h) / 2.0;
+    } else {
+        rect.y = rect.y.clamp(bounds.y, bounds.y + bounds.h - rect.h);
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FollowMode {
+    /// Jump straight to the target, like `pan_to`
+    Snap,
+    /// Exponential approach, `speed` is a rate: every second the camera closes
+    /// `1 - e^-speed` of the distance to the target, so 1.0 closes about 63%
+    Lerp { speed: f32 },
+    /// Critically damped spring, catches up in roughly `time` seconds without overshooting
+    Spring { time: f32 },
+}
+
+/// How a camera follows its target, see `Camera::follow`
+#[derive(Debug, Copy, Clone)]
+pub struct Follow {
+    pub mode: FollowMode,
+    /// Size of the area in the view the target can move in without the camera following,
+    /// centered in the view. `None` uses the player box.
+    pub dead_zone: Option<(f32, f32)>,
+    /// Seconds of the target's velocity the camera looks ahead by, per axis
+    pub look_ahead: (f32, f32),
+    /// Velocity of the spring
+    velocity: (f32, f32),
+}
+
+impl Follow {
+    pub fn new(mode: FollowMode) -> Follow {
+        Follow {
+            mode,
+            dead_zone: None,
+            look_ahead: (0.0, 0.0),
+            velocity: (0.0, 0.0),
+        }
+    }
+
+    /// Move a camera rect towards a target. `player_box` is the default dead zone.
+    pub fn step(&mut self, cam: &mut Rect<f32>, player_box: &Rect<u32>, target: &Rect<f32>, velocity: (f32, f32), dt: f32) {
+        let zone = match self.dead_zone {
+            Some((w, h)) => Rect { x: (cam.w - w) / 2.0, y: (cam.h - h) / 2.0, w, h },
+            None => Rect {
+                x: player_box.x as f32,
+                y: player_box.y as f32,
+                w: player_box.w as f32,
+                h: player_box.h as f32
+            },
+        };
+
+        let target_x = target.x + velocity.0 * self.look_ahead.0;
+        let target_y = target.y + velocity.1 * self.look_ahead.1;
+
+        // Camera position that just brings the target back into the dead zone
+        let goal = (
+            Follow::goal(cam.x, zone.x, zone.w, target_x, target.w),
+            Follow::goal(cam.y, zone.y, zone.h, target_y, target.h),
+        );
+
+        match self.mode {
+            FollowMode::Snap => {
+                cam.x = goal.0;
+                cam.y = goal.1;
+            }
+            FollowMode::Lerp { speed } => {
+                // Same approach at any frame rate, `dt` steps compose to `1 - e^(-speed * total)`
+                let t = 1.0 - (-speed * dt).exp();
+                cam.x += (goal.0 - cam.x) * t;
+                cam.y += (goal.1 - cam.y) * t;
+            }
+            FollowMode::Spring { time } => {
+                let (x, vx) = Follow::spring(cam.x, goal.0, self.velocity.0, time, dt);
+                let (y, vy) = Follow::spring(cam.y, goal.1, self.velocity.1, time, dt);
+                cam.x = x;
+                cam.y = y;
+                self.velocity = (vx, vy);
+            }
+        }
+    }
+
+    /// Stop the spring on axes where the camera was put somewhere other than where `step` left
+    /// it, like against bounds or a locked axis. Otherwise it keeps winding up while held and
+    /// slings the camera out once let go.
+    pub fn hold(&mut self, stepped: (f32, f32), placed: (f32, f32)) {
+        if placed.0 != stepped.0 { self.velocity.0 = 0.0; }
+        if placed.1 != stepped.1 { self.velocity.1 = 0.0; }
+    }
+
+    /// Camera position along one axis that puts the target inside the zone
+    fn goal(cam: f32, zone: f32, zone_size: f32, target: f32, target_size: f32) -> f32 {
+        let low = cam + zone;
+        let high = low + zone_size;
+
+        if target < low {
+            cam - (low - target)
+        } else if target + target_size > high {
+            cam + (target + target_size - high)
+        } else {
+            cam
+        }
+    }
+
+    /// One step of a critically damped spring, returning the new position and velocity
+    fn spring(from: f32, to: f32, velocity: f32, time: f32, dt: f32) -> (f32, f32) {
+        let omega = 2.0 / time.max(0.0001);
+        let x = omega * dt;
+        // Approximation of exp(-x) that stays stable for large steps
+        let decay = 1.0 / (1.0 + x + 0.48 * x * x + 0.235 * x * x * x);
+
+        let change = from - to;
+        let temp = (velocity + omega * change) * dt;
+
+        (to + (change + temp) * decay, (velocity - omega * temp) * decay)
+    }
 }
 
 #[derive(Debug)]
 pub struct FixedHeightCamera {
     pub rect: Rect<f32>,
     pub player_box: Rect<u32>,
+    pub follow: Follow,
+    /// Axes the camera doesn't pan along, (horizontal, vertical). Side-scrollers that keep
+    /// the view at one height lock the vertical axis.
+    pub lock: (bool, bool),
+    /// Level the view has to stay in, see `Camera::set_bounds`
+    pub bounds: Option<Rect<f32>>,
     zoom: f32,
     physical_size: (u32, u32),
     logical_size: (f32, f32),
@@ -35,6 +192,9 @@ impl Camera for FixedHeightCamera {
         let mut cam = Self {
             rect,
             player_box,
+            follow: Follow::new(FollowMode::Snap),
+            lock: (false, false),
+            bounds: None,
             zoom,
             physical_size,
             logical_size,
@@ -48,6 +208,19 @@ impl Camera for FixedHeightCamera {
 
     fn rect(&self) -> &Rect<f32> { &self.rect }
 
+    fn set_position(&mut self, x: f32, y: f32) {
+        self.rect.x = x;
+        self.rect.y = y;
+        clamp_to_bounds(&mut self.rect, &self.bounds);
+    }
+
+    /// The width always follows from the height and the shape of the window, so `w` is unused
+    fn set_size(&mut self, _w: f32, h: f32) {
+        self.rect.h = h;
+        self.calculate_pixel_width();
+        clamp_to_bounds(&mut self.rect, &self.bounds);
+    }
+
     fn matrix(&self) -> Matrix4<f32> {
         // Converts game pixel values into coordinate values
         let width_factor =  self.pixel_size * self.zoom / self.logical_size.0  as f32 * 2.0;
@@ -71,6 +244,7 @@ impl Camera for FixedHeightCamera {
         self.logical_size = (physical_size.0 as f32 / scale_factor, physical_size.1 as f32 / scale_factor);
         self.scale_factor = scale_factor;
         self.calculate_pixel_width();
+        clamp_to_bounds(&mut self.rect, &self.bounds);
     }
 
     fn viewport(&self) -> ([u32; 2], [u32; 2]) {
@@ -85,20 +259,55 @@ impl Camera for FixedHeightCamera {
 
 
     fn pan_to(&mut self, rect: &Rect<f32>) {
-        let left = self.rect.x as f32 + self.player_box.x as f32;
+        let left = self.rect.x + self.player_box.x as f32;
         let right = left + self.player_box.w as f32;
+        let top = self.rect.y + self.player_box.y as f32;
+        let bottom = top + self.player_box.h as f32;
 
         // Sides of the rectangle
         let rect_left = rect.x;
         let rect_right = rect_left + rect.w;
+        let rect_top = rect.y;
+        let rect_bottom = rect_top + rect.h;
 
-        if rect_left < left {
-            self.rect.x -= left - rect_left;
+        if !self.lock.0 {
+            if rect_left < left {
+                self.rect.x -= left - rect_left;
+            }
+
+            if rect_right > right {
+                self.rect.x += rect_right - right;
+            }
         }
 
-        if rect_right > right {
-            self.rect.x += rect_right - right;
+        if !self.lock.1 {
+            if rect_top < top {
+                self.rect.y -= top - rect_top;
+            }
+
+            if rect_bottom > bottom {
+                self.rect.y += rect_bottom - bottom;
+            }
         }
+
+        clamp_to_bounds(&mut self.rect, &self.bounds);
+    }
+
+    fn follow(&mut self, rect: &Rect<f32>, velocity: (f32, f32), dt: f32) {
+        let (x, y) = (self.rect.x, self.rect.y);
+        self.follow.step(&mut self.rect, &self.player_box, rect, velocity, dt);
+        let stepped = (self.rect.x, self.rect.y);
+
+        if self.lock.0 { self.rect.x = x; }
+        if self.lock.1 { self.rect.y = y; }
+
+        clamp_to_bounds(&mut self.rect, &self.bounds);
+        self.follow.hold(stepped, (self.rect.x, self.rect.y));
+    }
+
+    fn set_bounds(&mut self, bounds: Option<Rect<f32>>) {
+        self.bounds = bounds;
+        clamp_to_bounds(&mut self.rect, &self.bounds);
     }
 }
 
@@ -106,6 +315,9 @@ impl Camera for FixedHeightCamera {
 pub struct FixedSizeCamera {
     pub rect: Rect<f32>,
     pub player_box: Rect<u32>,
+    pub follow: Follow,
+    /// Level the view has to stay in, see `Camera::set_bounds`
+    pub bounds: Option<Rect<f32>>,
     zoom: f32,
     logical_size: (f32, f32),
     physical_size: (u32, u32),
@@ -119,9 +331,11 @@ impl FixedSizeCamera {
     fn calculate_pixel_width(&mut self) {
         self.pixel_size = 10;
 
-        while self.logical_size.0 < (self.rect.w as u32*self.pixel_size) as f32 ||
+        // Views bigger than the window stay at one pixel per unit
+        while self.pixel_size > 1 && (
+            self.logical_size.0 < (self.rect.w as u32*self.pixel_size) as f32 ||
             self.logical_size.1 < (self.rect.h as u32*self.pixel_size) as f32
-        {
+        ) {
             self.pixel_size-=1;
         }
 
@@ -143,6 +357,8 @@ impl Camera for FixedSizeCamera {
         let mut cam = FixedSizeCamera {
             rect,
             player_box,
+            follow: Follow::new(FollowMode::Snap),
+            bounds: None,
             scale_factor,
             logical_size,
             physical_size,
@@ -183,6 +399,7 @@ impl Camera for FixedSizeCamera {
         self.logical_size = (physical_size.0 as f32 / scale_factor, physical_size.1 as f32 / scale_factor);
         self.scale_factor = scale_factor;
         self.calculate_pixel_width();
+        clamp_to_bounds(&mut self.rect, &self.bounds);
     }
 
     fn viewport(&self) -> ([u32; 2], [u32; 2]) {
@@ -225,7 +442,77 @@ impl Camera for FixedSizeCamera {
         if rect_bottom > bottom {
             self.rect.y += rect_bottom - bottom;
         }
+
+        clamp_to_bounds(&mut self.rect, &self.bounds);
+    }
+
+    fn follow(&mut self, rect: &Rect<f32>, velocity: (f32, f32), dt: f32) {
+        self.follow.step(&mut self.rect, &self.player_box, rect, velocity, dt);
+        let stepped = (self.rect.x, self.rect.y);
+
+        clamp_to_bounds(&mut self.rect, &self.bounds);
+        self.follow.hold(stepped, (self.rect.x, self.rect.y));
+    }
+
+    fn set_bounds(&mut self, bounds: Option<Rect<f32>>) {
+        self.bounds = bounds;
+        clamp_to_bounds(&mut self.rect, &self.bounds);
     }
 
     fn rect(&self) -> &Rect<f32> { &self.rect }
+
+    fn set_position(&mut self, x: f32, y: f32) {
+        self.rect.x = x;
+        self.rect.y = y;
+        clamp_to_bounds(&mut self.rect, &self.bounds);
+    }
+
+    fn set_size(&mut self, w: f32, h: f32) {
+        self.rect.w = w;
+        self.rect.h = h;
+        self.calculate_pixel_width();
+        clamp_to_bounds(&mut self.rect, &self.bounds);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spring_does_not_wind_up_against_bounds() {
+        let mut cam = FixedSizeCamera::new(Rect::new(0.0, 0.0, 100.0, 50.0), Rect::new(40, 20, 20, 10), 1.0, (400, 200), 1.0);
+        cam.follow = Follow::new(FollowMode::Spring { time: 0.5 });
+        cam.set_bounds(Some(Rect::new(0.0, 0.0, 300.0, 50.0)));
+
+        for _ in 0..120 {
+            cam.follow(&Rect::new(1000.0, 20.0, 10.0, 10.0), (0.0, 0.0), 1.0 / 60.0);
+        }
+        assert_eq!(cam.rect.x, 200.0);
+
+        // Let go with the target already in the dead zone, the camera has nowhere to go
+        cam.set_bounds(None);
+        for _ in 0..60 {
+            cam.follow(&Rect::new(250.0, 20.0, 10.0, 10.0), (0.0, 0.0), 1.0 / 60.0);
+        }
+        assert_eq!(cam.rect.x, 200.0);
+    }
+
+    #[test]
+    fn spring_does_not_wind_up_on_locked_axis() {
+        let mut cam = FixedHeightCamera::new(Rect::new(0.0, 0.0, 100.0, 50.0), Rect::new(40, 20, 20, 10), 1.0, (400, 200), 1.0);
+        cam.follow = Follow::new(FollowMode::Spring { time: 0.5 });
+        cam.lock = (true, false);
+
+        for _ in 0..60 {
+            cam.follow(&Rect::new(1000.0, 20.0, 10.0, 10.0), (0.0, 0.0), 1.0 / 60.0);
+        }
+        assert_eq!(cam.rect.x, 0.0);
+
+        cam.lock = (false, false);
+        for _ in 0..60 {
+            cam.follow(&Rect::new(45.0, 20.0, 10.0, 10.0), (0.0, 0.0), 1.0 / 60.0);
+        }
+        assert_eq!(cam.rect.x, 0.0);
+    }
 }
@@ -0,0 +1,172 @@
+use crate::rect::Rect;
+use crate::graphics::vulkan::Vertex;
+use crate::physics::{Shape, DebugShape, CollisionMap, TileKind};
+
+pub const HITBOX_COLOR: [f32; 4] = [0.0, 1.0, 0.0, 1.0];
+pub const SWEEP_COLOR: [f32; 4] = [1.0, 1.0, 0.0, 0.5];
+pub const CONTACT_COLOR: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+pub const TILE_COLOR: [f32; 4] = [0.0, 0.5, 1.0, 0.8];
+
+/// Number of lines making up a circle
+const CIRCLE_SEGMENTS: usize = 16;
+
+/// Length of drawn contact normals
+const NORMAL_LENGTH: f32 = 8.0;
+
+/// Colored lines drawn on top of everything else. Lines are queued during a frame, drawn by the
+/// next `GraphicsSystem::transfer` and then cleared. Nothing is queued while disabled.
+#[derive(Debug)]
+pub struct DebugDraw {
+    pub enabled: bool,
+    /// Width of the lines in world units
+    pub thickness: f32,
+    lines: Vec<((f32, f32), (f32, f32), [f32; 4])>,
+}
+
+impl Default for DebugDraw {
+    fn default() -> DebugDraw {
+        DebugDraw::new()
+    }
+}
+
+impl DebugDraw {
+    pub fn new() -> DebugDraw {
+        DebugDraw {
+            enabled: false,
+            thickness: 1.0,
+            lines: Vec::new(),
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+        self.lines.clear();
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+    }
+
+    pub fn line(&mut self, from: (f32, f32), to: (f32, f32), color: [f32; 4]) {
+        if self.enabled {
+            self.lines.push((from, to, color));
+        }
+    }
+
+    /// Closed outline through the points
+    pub fn polygon(&mut self, points: &[(f32, f32)], color: [f32; 4]) {
+        for k in 0..points.len() {
+            self.line(points[k], points[(k + 1) % points.len()], color);
+        }
+    }
+
+    pub fn rect(&mut self, rect: &Rect<f32>, color: [f32; 4]) {
+        self.polygon(&[
+            (rect.x, rect.y),
+            (rect.x + rect.w, rect.y),
+            (rect.x + rect.w, rect.y + rect.h),
+            (rect.x, rect.y + rect.h),
+        ], color);
+    }
+
+    pub fn circle(&mut self, center: (f32, f32), r: f32, color: [f32; 4]) {
+        let points: Vec<(f32, f32)> = (0..CIRCLE_SEGMENTS)
+            .map(|k| {
+                let angle = k as f32 / CIRCLE_SEGMENTS as f32 * std::f32::consts::TAU;
+                (center.0 + angle.cos() * r, center.1 + angle.sin() * r)
+            })
+            .collect();
+
+        self.polygon(&points, color);
+    }
+
+    pub fn shape(&mut self, shape: &Shape, color: [f32; 4]) {
+        match *shape {
+            Shape::Aabb(rect) => self.rect(&rect, color),
+            Shape::Circle { x, y, r } => self.circle((x, y), r, color),
+            Shape::Capsule { a, b, r } => {
+                self.circle(a, r, color);
+                self.circle(b, r, color);
+
+                let len = ((b.0 - a.0).powi(2) + (b.1 - a.1).powi(2)).sqrt();
+                if len > 0.0 {
+                    let side = ((a.1 - b.1) / len * r, (b.0 - a.0) / len * r);
+                    self.line((a.0 + side.0, a.1 + side.1), (b.0 + side.0, b.1 + side.1), color);
+                    self.line((a.0 - side.0, a.1 - side.1), (b.0 - side.0, b.1 - side.1), color);
+                }
+            }
+        }
+    }
+
+    /// Queue what the physics system recorded during its last tick
+    pub fn physics(&mut self, shapes: &[DebugShape]) {
+        for shape in shapes {
+            match *shape {
+                DebugShape::Hitbox(shape) => self.shape(&shape, HITBOX_COLOR),
+                DebugShape::Sweep(rect) => self.rect(&rect, SWEEP_COLOR),
+                DebugShape::Contact { point, normal } => self.line(
+                    point,
+                    (point.0 + normal.0 * NORMAL_LENGTH, point.1 + normal.1 * NORMAL_LENGTH),
+                    CONTACT_COLOR
+                ),
+            }
+        }
+    }
+
+    /// Queue the outline of every non-empty tile inside `area`. Maps without a tile size can
+    /// only be tested, not inspected, so nothing is drawn for them.
+    pub fn map<CM: CollisionMap>(&mut self, map: &CM, area: &Rect<f32>) {
+        let size = match map.tile_size() {
+            Some(size) if self.enabled => size,
+            _ => return
+        };
+
+        let x0 = (area.x / size).floor() as i32;
+        let y0 = (area.y / size).floor() as i32;
+        let x1 = ((area.x + area.w) / size).floor() as i32;
+        let y1 = ((area.y + area.h) / size).floor() as i32;
+
+        for ty in y0..=y1 {
+            for tx in x0..=x1 {
+                let trect = Rect::new(tx as f32 * size, ty as f32 * size, size, size);
+                let bottom = trect.y + trect.h;
+
+                match map.tile(tx, ty) {
+                    TileKind::Empty => {}
+                    TileKind::Solid => self.rect(&trect, TILE_COLOR),
+                    TileKind::OneWayUp => self.line((trect.x, trect.y), (trect.x + trect.w, trect.y), TILE_COLOR),
+                    kind => self.polygon(&[
+                        (trect.x, bottom - kind.floor(0.0).unwrap_or(0.0) * size),
+                        (trect.x + trect.w, bottom - kind.floor(1.0).unwrap_or(0.0) * size),
+                        (trect.x + trect.w, bottom),
+                        (trect.x, bottom),
+                    ], TILE_COLOR),
+                }
+            }
+        }
+    }
+
+    /// Add a quad for every queued line
+    pub(crate) fn append(&self, vertices: &mut Vec<Vertex>, indices: &mut Vec<u32>, mut vert_index: u32) {
+        for &(from, to, color) in self.lines.iter() {
+            let len = ((to.0 - from.0).powi(2) + (to.1 - from.1).powi(2)).sqrt();
+            if len == 0.0 { continue; }
+
+            let half = self.thickness / 2.0;
+            let side = ((from.1 - to.1) / len * half, (to.0 - from.0) / len * half);
+
+            // Negative texture coordinates draw the plain color
+            for (x, y) in [
+                (from.0 + side.0, from.1 + side.1),
+                (to.0 + side.0, to.1 + side.1),
+                (to.0 - side.0, to.1 - side.1),
+                (from.0 - side.0, from.1 - side.1),
+            ] {
+                vertices.push(Vertex { position: [x, y], tex_coords: [-1.0, -1.0], color });
+            }
+
+            indices.extend_from_slice(&[vert_index, vert_index+1, vert_index+2, vert_index+2, vert_index+3, vert_index]);
+            vert_index += 4;
+        }
+    }
+}
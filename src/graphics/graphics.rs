@@ -3,8 +3,13 @@ use winit::event_loop::EventLoop;
 use super::atlas::Atlas;
 use crate::component::{GraphicsComponent, PositionComponent, PhysicsComponent};
 use crate::graphics::Camera;
-use crate::rect::Rect;
+use crate::rect::{Rect, Quad, quad_vertices};
 use super::atlas::Texture;
+use crate::animation::Skeleton;
+use super::debug::DebugDraw;
+use crate::physics::{PhysicsSystem, CollisionMap};
+use crate::world::World;
+use crate::query::query;
 
 struct Position {x: f32, y: f32}
 impl PositionComponent for Position {
@@ -18,6 +23,9 @@ pub struct GraphicsSystem<C: Camera> {
     vulkan_state: VulkanState,
     atlas: Atlas,
     pub camera: C,
+    pub debug: DebugDraw,
+    /// Bone sprites queued for the next transfer, with the y they are depth sorted by
+    quads: Vec<(f32, Texture, Quad)>,
 }
 
 impl<C: Camera> GraphicsSystem<C> {
@@ -41,6 +49,8 @@ impl<C: Camera> GraphicsSystem<C> {
             vulkan_state,
             atlas,
             camera,
+            debug: DebugDraw::new(),
+            quads: Vec::new(),
         }
     }
 
@@ -48,6 +58,10 @@ impl<C: Camera> GraphicsSystem<C> {
         self.vulkan_state.recreate_swapchain = true;
     }
 
+    pub fn atlas(&self) -> &Atlas {
+        &self.atlas
+    }
+
     pub fn get_texture(&self, path: &str) -> Option<Texture> {
         self.atlas.get(path)
     }
@@ -68,18 +82,17 @@ impl<C: Camera> GraphicsSystem<C> {
         let mut vertices = Vec::with_capacity(p.len() * 4);
         let mut indices = Vec::with_capacity(p.len() * 6);
 
-        // Create vec of all entity rectangles
+        // Create vec of all entity rectangles, keyed by their bottom
         rects.extend(
-            (0..p.len())
-                .filter(|i| p[*i].is_some() && g[*i].is_some())
-                .map(|i| {
-                    let pos = p[i].as_ref().unwrap();
-                    (i, g[i].as_ref().unwrap().renderbox().after_position(pos))
-                })
+            query((p, g)).map(|(_, (pos, graphics))| {
+                let rect = graphics.renderbox().after_position(pos);
+                (rect.y + rect.h, graphics.texture(), rect.corners(), graphics.tint())
+            })
         );
+        rects.extend(self.quads.drain(..).map(|(y, tex, corners)| (y, tex, corners, [1.0; 4])));
 
-        // Sort by bottom of rectangle
-        rects.sort_unstable_by(|a, b| (a.1.y+a.1.h).partial_cmp(&(b.1.y+b.1.h)).unwrap());
+        // Sort by bottom of rectangle, stable so the bones of a skeleton keep their order
+        rects.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
 
         let mut vert_index = 0;
 
@@ -87,15 +100,15 @@ impl<C: Camera> GraphicsSystem<C> {
         for bg in bgs {
             let bg_rect = bg.renderbox().after_position(&Position{ x: 0.0, y: 0.0 });
             let bg_tex = bg.texture();
-            vertices.extend_from_slice(&bg_rect.vertices(&bg_tex, &self.atlas));
+            vertices.extend_from_slice(&bg_rect.tinted_vertices(&bg_tex, &self.atlas, bg.tint()));
             indices.extend_from_slice(&bg_rect.indices(vert_index));
             vert_index += 4;
         }
 
         // Add ordinary objects
-        for i in 0..rects.len() {
-            vertices.extend_from_slice(&rects[i].1.vertices(&g[rects[i].0].as_ref().unwrap().texture(), &self.atlas));
-            indices.extend_from_slice(&rects[i].1.indices(vert_index));
+        for (_, tex, corners, tint) in rects.iter() {
+            vertices.extend_from_slice(&quad_vertices(*corners, tex, &self.atlas, *tint));
+            indices.extend_from_slice(&[vert_index, vert_index+1, vert_index+2, vert_index+2, vert_index+3, vert_index]);
             vert_index += 4;
         }
 
@@ -105,14 +118,57 @@ impl<C: Camera> GraphicsSystem<C> {
         for ov in overlays {
             let ov_rect = ov.renderbox().after_position(&Position {x: cam.x, y: cam.y});
             let ov_tex = ov.texture();
-            vertices.extend_from_slice(&ov_rect.vertices(&ov_tex, &self.atlas));
+            vertices.extend_from_slice(&ov_rect.tinted_vertices(&ov_tex, &self.atlas, ov.tint()));
             indices.extend_from_slice(&ov_rect.indices(vert_index));
             vert_index += 4;
         }
 
+        // Debug lines go on top of everything
+        self.debug.append(&mut vertices, &mut indices, vert_index);
+        self.debug.clear();
+
         self.vulkan_state.transfer_object_data(vertices, indices);
     }
 
+    /// `transfer` over the components of a world
+    pub fn transfer_world<P, G, GH, GPH>(&mut self, bgs: &Vec<GH>, world: &World, overlays: &Vec<GPH>)
+    where
+        P: PositionComponent + 'static,
+        G: GraphicsComponent + 'static,
+        GH: GraphicsComponent,
+        GPH: GraphicsComponent,
+    {
+        self.transfer(bgs, &world.borrow::<P>(), &world.borrow::<G>(), overlays);
+    }
+
+    /// Queue the bone sprites of a skeleton placed at a position for the next transfer. They
+    /// are sorted with the other entities by the y of the position, so it should be the feet.
+    pub fn skeleton<P: PositionComponent>(&mut self, skeleton: &Skeleton, position: &P) {
+        let y = position.y();
+        self.quads.extend(
+            skeleton.quads((position.x(), y)).into_iter().map(|(tex, corners)| (y, tex, corners))
+        );
+    }
+
+    /// Queue the skeletons of every entity for the next transfer
+    pub fn skeletons<P: PositionComponent>(&mut self, p: &Vec<Option<P>>, s: &Vec<Option<Skeleton>>) {
+        for (_, (pos, skeleton)) in query((p, s)) {
+            self.skeleton(skeleton, pos);
+        }
+    }
+
+    /// Queue the hitboxes, sweeps and contacts of the last physics tick for the next transfer,
+    /// see `PhysicsSystem::set_debug`
+    pub fn debug_physics(&mut self, physics: &PhysicsSystem) {
+        self.debug.physics(physics.debug_shapes());
+    }
+
+    /// Queue the tiles of a collision map visible to the camera for the next transfer
+    pub fn debug_map<CM: CollisionMap>(&mut self, map: &CM) {
+        let cam = *self.camera.rect();
+        self.debug.map(map, &cam);
+    }
+
     pub fn draw(&mut self) {
         self.vulkan_state.draw(&mut self.camera);
     }
@@ -130,6 +186,22 @@ impl<C: Camera> GraphicsSystem<C> {
         }
     }
 
+    /// Ease the camera towards an entity moving at `velocity`, see `Camera::follow`
+    pub fn follow<P: PositionComponent, PH: PhysicsComponent>(
+        &mut self,
+        p: &P,
+        ph: Option<&PH>,
+        velocity: (f32, f32),
+        dt: f32
+    ) {
+        let target = match ph {
+            Some(ph) => ph.hitbox().after_position(p),
+            None => Rect { x: p.x(), y: p.y(), w: 0.0, h: 0.0 },
+        };
+
+        self.camera.follow(&target, velocity, dt);
+    }
+
     pub fn update_atlas(&mut self, atlas: Atlas) {
         self.atlas = atlas;
         self.vulkan_state.update_atlas(&self.atlas);
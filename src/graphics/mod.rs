@@ -1,10 +1,13 @@
 pub mod atlas;
+mod aseprite;
 pub mod camera;
 pub mod vulkan;
 pub mod graphics;
 pub mod text;
+pub mod debug;
 
 pub use self::atlas::Atlas;
 pub use self::atlas::Texture;
 pub use self::camera::*;
 pub use self::graphics::GraphicsSystem;
+pub use self::debug::DebugDraw;
@@ -1,10 +1,15 @@
 #version 450
 layout(location = 0) in vec2 v_tex_coords;
+layout(location = 1) in vec4 v_color;
 
 layout(location = 0) out vec4 f_color;
 
 layout(set = 0, binding = 1) uniform sampler2D atlas;
 
 void main() {
-    f_color = texture(atlas, v_tex_coords);
+    if (v_tex_coords.x < 0.0) {
+        f_color = v_color;
+    } else {
+        f_color = texture(atlas, v_tex_coords) * v_color;
+    }
 }
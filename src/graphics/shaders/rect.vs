@@ -2,8 +2,10 @@
 
 layout(location = 0) in vec2 position;
 layout(location = 1) in vec2 tex_coords;
+layout(location = 2) in vec4 color;
 
 layout(location = 0) out vec2 v_tex_coords;
+layout(location = 1) out vec4 v_color;
 
 layout(set = 0, binding = 0) uniform Data {
     mat4 worldview;
@@ -11,6 +13,7 @@ layout(set = 0, binding = 0) uniform Data {
 
 void main() {
     v_tex_coords = tex_coords;
+    v_color = color;
     vec4 pos = uniforms.worldview * vec4(position, 1.0, 1.0);
     gl_Position = pos;
-}
\ No newline at end of file
+}
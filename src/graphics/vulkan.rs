@@ -83,9 +83,12 @@ use bytemuck::{Pod, Zeroable};
 #[derive(Default, Debug, Clone, Copy, Pod, Zeroable)]
 pub struct Vertex {
     pub position: [f32; 2],
-    pub tex_coords: [f32; 2]
+    /// Negative coordinates draw `color` as is instead of sampling the atlas
+    pub tex_coords: [f32; 2],
+    /// Multiplied with the texture color
+    pub color: [f32; 4]
 }
-vulkano::impl_vertex!(Vertex, position, tex_coords);
+vulkano::impl_vertex!(Vertex, position, tex_coords, color);
 
 pub struct VulkanState {
     _instance: Arc<Instance>,
@@ -105,11 +108,12 @@ pub struct VulkanState {
     _fs: Arc<ShaderModule>,
 
     vertex_buffer_pool: CpuBufferPool<Vertex>,
-    index_buffer_pool: CpuBufferPool<u16>,
+    /// 32 bit indices, debug lines alone can take more than 16k quads
+    index_buffer_pool: CpuBufferPool<u32>,
     uniform_buffer_pool: CpuBufferPool<vs::ty::Data>,
 
     vertex_buffer: Arc<CpuBufferPoolChunk<Vertex, Arc<StdMemoryPool>>>,
-    index_buffer: Arc<CpuBufferPoolChunk<u16, Arc<StdMemoryPool>>>,
+    index_buffer: Arc<CpuBufferPoolChunk<u32, Arc<StdMemoryPool>>>,
 
     pub recreate_swapchain: bool,
     pub previous_frame_end: Option<Box<dyn GpuFuture>>,
@@ -423,7 +427,7 @@ impl VulkanState {
     where
         I: IntoIterator<Item = Vertex>,
         I::IntoIter: ExactSizeIterator,
-        J: IntoIterator<Item = u16>,
+        J: IntoIterator<Item = u32>,
         J::IntoIter: ExactSizeIterator,
     {
         self.vertex_buffer = self.vertex_buffer_pool.chunk(vertices).unwrap();
@@ -1,7 +1,16 @@
 pub mod rect;
+pub mod fixed;
 pub mod component;
 pub mod physics;
 pub mod animation;
 pub mod graphics;
+pub mod world;
+pub mod query;
+pub mod app;
+pub mod events;
+pub mod tween;
 
 pub use rect::Rect;
+pub use fixed::Fixed;
+pub use world::{World, Entity};
+pub use app::App;
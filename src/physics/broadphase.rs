@@ -0,0 +1,84 @@
+use std::collections::HashMap;
+
+use crate::rect::Rect;
+
+/// Uniform grid bucketing entity indices by the cells their bounds cover
+#[derive(Debug)]
+pub struct SpatialGrid {
+    cell: f32,
+    cells: HashMap<(i32, i32), Vec<usize>>,
+    /// Range of cells holding entities, queries never look outside of it
+    extent: Option<(i32, i32, i32, i32)>,
+}
+
+impl SpatialGrid {
+    pub fn new(cell: f32) -> SpatialGrid {
+        SpatialGrid {
+            cell,
+            cells: HashMap::new(),
+            extent: None,
+        }
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        self.cell
+    }
+
+    /// Remove every entity, keeping the allocated buckets around for reuse
+    pub fn clear(&mut self) {
+        for bucket in self.cells.values_mut() {
+            bucket.clear();
+        }
+        self.extent = None;
+    }
+
+    /// Entities with non-finite bounds cover no cell and are left out
+    pub fn insert(&mut self, index: usize, bounds: &Rect<f32>) {
+        if ![bounds.x, bounds.y, bounds.w, bounds.h].iter().all(|v| v.is_finite()) { return; }
+
+        let (x0, y0, x1, y1) = self.range(bounds);
+        self.extent = Some(match self.extent {
+            Some((ex0, ey0, ex1, ey1)) => (ex0.min(x0), ey0.min(y0), ex1.max(x1), ey1.max(y1)),
+            None => (x0, y0, x1, y1),
+        });
+
+        for y in y0..=y1 {
+            for x in x0..=x1 {
+                self.cells.entry((x, y)).or_default().push(index);
+            }
+        }
+    }
+
+    /// Collect every entity sharing a cell with `bounds` into `out`, sorted by index so
+    /// results never depend on hashing order
+    pub fn query(&self, bounds: &Rect<f32>, out: &mut Vec<usize>) {
+        out.clear();
+
+        // Huge bounds, like those of a long raycast, only walk the cells in use
+        let (ex0, ey0, ex1, ey1) = match self.extent {
+            Some(extent) => extent,
+            None => return,
+        };
+        let (x0, y0, x1, y1) = self.range(bounds);
+
+        for y in y0.max(ey0)..=y1.min(ey1) {
+            for x in x0.max(ex0)..=x1.min(ex1) {
+                if let Some(bucket) = self.cells.get(&(x, y)) {
+                    out.extend_from_slice(bucket);
+                }
+            }
+        }
+
+        out.sort_unstable();
+        out.dedup();
+    }
+
+    fn range(&self, bounds: &Rect<f32>) -> (i32, i32, i32, i32) {
+        (
+            (bounds.x / self.cell).floor() as i32,
+            (bounds.y / self.cell).floor() as i32,
+            ((bounds.x + bounds.w) / self.cell).floor() as i32,
+            ((bounds.y + bounds.h) / self.cell).floor() as i32,
+        )
+    }
+}
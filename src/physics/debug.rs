@@ -0,0 +1,13 @@
+use crate::rect::Rect;
+use crate::physics::Shape;
+
+/// Something the physics system worked with last tick, recorded for drawing while debugging
+#[derive(Debug, Copy, Clone)]
+pub enum DebugShape {
+    /// Shape of an entity where it ended up
+    Hitbox(Shape),
+    /// Area a moving entity was tested against along one axis, `after_x` or `after_y`
+    Sweep(Rect<f32>),
+    /// Normal of a contact, starting at roughly where the entities touch
+    Contact { point: (f32, f32), normal: (f32, f32) },
+}
@@ -0,0 +1,100 @@
+use std::collections::BTreeMap;
+
+use crate::rect::Rect;
+use crate::world::Entity;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ContactState {
+    /// The entities touched for the first time this tick
+    Started,
+    /// The entities were already touching last tick
+    Persisting,
+    /// The entities stopped touching this tick
+    Ended,
+}
+
+/// Contact between two entities, `a` is always the smaller index
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CollisionEvent {
+    pub a: usize,
+    pub b: usize,
+    pub state: ContactState,
+    /// Normal of the contact surface on `b`, pointing towards `a`
+    pub normal: (f32, f32),
+    /// Ids of `a` and `b` when the system ran over a `World`. Indices are reused after a
+    /// despawn, these tell the entities apart.
+    pub entities: Option<(Entity, Entity)>,
+}
+
+/// Ids of the two entities of a contact, if known
+type Ids = Option<(Entity, Entity)>;
+
+/// Keeps track of which pairs of entities touch between ticks
+#[derive(Debug, Default)]
+pub(crate) struct Contacts {
+    /// Contacts of the last tick, along with the ids of the pair back then
+    previous: BTreeMap<(usize, usize), ((f32, f32), Ids)>,
+    current: BTreeMap<(usize, usize), (f32, f32)>,
+}
+
+impl Contacts {
+    /// Record a contact this tick, with the normal pointing from `b` towards `a`
+    pub(crate) fn insert(&mut self, a: usize, b: usize, normal: (f32, f32)) {
+        if a < b {
+            self.current.insert((a, b), normal);
+        } else {
+            self.current.insert((b, a), (-normal.0, -normal.1));
+        }
+    }
+
+    /// Record a contact this tick unless the pair already has one, keeping the normal found
+    /// while resolving movement
+    pub(crate) fn touch(&mut self, a: usize, b: usize, normal: (f32, f32)) {
+        let key = if a < b { (a, b) } else { (b, a) };
+        if !self.current.contains_key(&key) {
+            self.insert(a, b, normal);
+        }
+    }
+
+    /// Compare this tick's contacts against the last tick's and emit the events. `ids` holds
+    /// the entity at every index when running over a `World` and is empty otherwise, a pair
+    /// whose ids changed is a new contact.
+    pub(crate) fn finish(&mut self, events: &mut Vec<CollisionEvent>, ids: &[Option<Entity>]) {
+        let pair = |a: usize, b: usize| ids.get(a).copied().flatten().zip(ids.get(b).copied().flatten());
+
+        for (&(a, b), &normal) in self.current.iter() {
+            let entities = pair(a, b);
+            let state = match self.previous.get(&(a, b)) {
+                Some(&(_, before)) if before == entities => ContactState::Persisting,
+                _ => ContactState::Started,
+            };
+
+            events.push(CollisionEvent { a, b, state, normal, entities });
+        }
+
+        for (&(a, b), &(normal, entities)) in self.previous.iter() {
+            if !self.current.contains_key(&(a, b)) || pair(a, b) != entities {
+                events.push(CollisionEvent { a, b, state: ContactState::Ended, normal, entities });
+            }
+        }
+
+        self.previous = std::mem::take(&mut self.current).into_iter()
+            .map(|((a, b), normal)| ((a, b), (normal, pair(a, b))))
+            .collect();
+    }
+}
+
+/// Normal along the axis of least penetration between two overlapping rects, pointing from
+/// `b` towards `a`
+pub(crate) fn overlap_normal(a: &Rect<f32>, b: &Rect<f32>) -> (f32, f32) {
+    let dx = (a.x + a.w / 2.0) - (b.x + b.w / 2.0);
+    let dy = (a.y + a.h / 2.0) - (b.y + b.h / 2.0);
+    let overlap_x = (a.w + b.w) / 2.0 - dx.abs();
+    let overlap_y = (a.h + b.h) / 2.0 - dy.abs();
+
+    if overlap_x < overlap_y {
+        (if dx < 0.0 { -1.0 } else { 1.0 }, 0.0)
+    } else {
+        (0.0, if dy < 0.0 { -1.0 } else { 1.0 })
+    }
+}
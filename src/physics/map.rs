@@ -0,0 +1,215 @@
+use crate::rect::Rect;
+
+const TOLERANCE: f32 = 0.0001;
+
+/// Collision behaviour of a single tile
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TileKind {
+    Empty,
+    Solid,
+    /// Platform that can be jumped through from below but stood on from above
+    OneWayUp,
+    /// 45° slope with the floor rising towards the right
+    SlopeRight,
+    /// 45° slope with the floor rising towards the left
+    SlopeLeft,
+    /// Lower tile of a 22.5° slope rising towards the right
+    SlopeRightLow,
+    /// Upper tile of a 22.5° slope rising towards the right
+    SlopeRightHigh,
+    /// Lower tile of a 22.5° slope rising towards the left
+    SlopeLeftLow,
+    /// Upper tile of a 22.5° slope rising towards the left
+    SlopeLeftHigh,
+}
+
+impl TileKind {
+    /// Height of the floor measured up from the bottom of the tile, as a fraction of the tile
+    /// size, at fraction `fx` across the tile. Only slopes have a floor.
+    pub fn floor(&self, fx: f32) -> Option<f32> {
+        let fx = fx.clamp(0.0, 1.0);
+
+        match self {
+            TileKind::SlopeRight => Some(fx),
+            TileKind::SlopeLeft => Some(1.0 - fx),
+            TileKind::SlopeRightLow => Some(fx / 2.0),
+            TileKind::SlopeRightHigh => Some(0.5 + fx / 2.0),
+            TileKind::SlopeLeftLow => Some(0.5 - fx / 2.0),
+            TileKind::SlopeLeftHigh => Some(1.0 - fx / 2.0),
+            _ => None
+        }
+    }
+
+    pub fn is_slope(&self) -> bool {
+        self.floor(0.0).is_some()
+    }
+}
+
+pub trait CollisionMap {
+    fn test(&self, rect: &Rect<f32>) -> bool;
+
+    /// Size of a single square tile. Maps that only implement `test` leave this as `None`
+    /// and are resolved by zeroing velocity on contact.
+    fn tile_size(&self) -> Option<f32> { None }
+
+    /// Kind of the tile at the given tile coordinates
+    fn tile(&self, _x: i32, _y: i32) -> TileKind { TileKind::Empty }
+
+    /// Area covered by the map, rays and casts stop at its edge. Maps without bounds are
+    /// searched up to a fixed distance.
+    fn bounds(&self) -> Option<Rect<f32>> { None }
+
+    /// Collision categories of the map, only hitboxes whose mask includes them collide with it
+    fn category(&self) -> u32 { 1 }
+}
+
+pub struct Dummy {}
+impl CollisionMap for Dummy {
+    fn test(&self, _: &Rect<f32>) -> bool { false }
+}
+
+/// Tile coordinates covered by a rectangle, ignoring edges that only touch
+fn tile_range(rect: &Rect<f32>, size: f32) -> (i32, i32, i32, i32) {
+    (
+        ((rect.x + TOLERANCE) / size).floor() as i32,
+        ((rect.y + TOLERANCE) / size).floor() as i32,
+        ((rect.x + rect.w - TOLERANCE) / size).floor() as i32,
+        ((rect.y + rect.h - TOLERANCE) / size).floor() as i32,
+    )
+}
+
+fn tile_rect(x: i32, y: i32, size: f32) -> Rect<f32> {
+    Rect::new(x as f32 * size, y as f32 * size, size, size)
+}
+
+/// Resolve a moving rectangle against the tiles of a map.
+///
+/// Returns the distance the rect may still travel on each axis before touching a tile along
+/// with that tile, mirroring how entity collisions are reported. Slopes snap the foot of the
+/// rect (bottom center) onto their surface instead of blocking.
+pub(crate) fn resolve_tiles<CM: CollisionMap>(
+    map: &CM,
+    size: f32,
+    irect: &Rect<f32>,
+    after_x: &Rect<f32>,
+    after_y: &Rect<f32>,
+    vel: (f32, f32),
+    t: f32,
+) -> (Option<f32>, Option<Rect<f32>>, Option<f32>, Option<Rect<f32>>) {
+    let mut x_delta: Option<f32> = None;
+    let mut x_coll: Option<Rect<f32>> = None;
+    let mut y_delta: Option<f32> = None;
+    let mut y_coll: Option<Rect<f32>> = None;
+
+    let bottom = irect.y + irect.h;
+    let foot_x = irect.x + irect.w / 2.0;
+    let on_slope = map.tile(
+        (foot_x / size).floor() as i32,
+        ((bottom - TOLERANCE) / size).floor() as i32
+    ).is_slope() || map.tile(
+        (foot_x / size).floor() as i32,
+        ((bottom + TOLERANCE) / size).floor() as i32
+    ).is_slope();
+
+    // How far the foot may step up or be pulled down while following a slope
+    let dx = vel.0 * t;
+    let step = dx.abs() + if on_slope { irect.w / 2.0 } else { 0.0 } + TOLERANCE;
+    let reach = if on_slope { irect.w } else { 0.0 };
+
+    // Horizontal movement is only blocked by solid tiles. While walking on a slope the tiles
+    // near the foot are stepped over so the rect can climb onto the ledge at the top.
+    let (x0, y0, x1, y1) = tile_range(after_x, size);
+    for ty in y0..=y1 {
+        for tx in x0..=x1 {
+            if map.tile(tx, ty) != TileKind::Solid { continue; }
+
+            let trect = tile_rect(tx, ty, size);
+            if trect.has_intersection(irect) { continue; }
+            if on_slope && trect.y >= bottom - step { continue; }
+
+            let dist = if vel.0 >= 0.0 {
+                trect.x - (irect.x + irect.w)
+            } else {
+                (trect.x + trect.w) - irect.x
+            };
+
+            if x_delta.map_or(true, |d| dist.abs() < d.abs()) {
+                x_delta = Some(dist);
+                x_coll = Some(trect);
+            }
+        }
+    }
+
+    // Vertical movement is blocked by solid tiles and by one way platforms the rect was
+    // standing above before moving down. On a slope only the foot decides where the floor is.
+    let foot_tx = ((foot_x + dx) / size).floor() as i32;
+    let (x0, y0, x1, y1) = tile_range(after_y, size);
+    for ty in y0..=y1 {
+        for tx in x0..=x1 {
+            if on_slope && vel.1 >= 0.0 && tx != foot_tx { continue; }
+
+            let kind = map.tile(tx, ty);
+            let trect = tile_rect(tx, ty, size);
+
+            let blocks = match kind {
+                TileKind::Solid => !trect.has_intersection(irect),
+                TileKind::OneWayUp => vel.1 > 0.0 && bottom <= trect.y + TOLERANCE,
+                _ => false
+            };
+            if !blocks { continue; }
+
+            let dist = if vel.1 >= 0.0 {
+                trect.y - bottom
+            } else {
+                (trect.y + trect.h) - irect.y
+            };
+
+            if y_delta.map_or(true, |d| dist.abs() < d.abs()) {
+                y_delta = Some(dist);
+                y_coll = Some(trect);
+            }
+        }
+    }
+
+    // Snap the foot onto slopes (and the ledges at their top) when falling or walking along
+    // them, never while moving upwards
+    if vel.1 >= 0.0 {
+        let foot_x = foot_x + x_delta.unwrap_or(dx);
+        let foot_y = bottom + y_delta.unwrap_or(vel.1 * t);
+        let tx = (foot_x / size).floor() as i32;
+
+        let mut ground: Option<(f32, Rect<f32>)> = None;
+        let ty0 = ((bottom - step) / size).floor() as i32;
+        let ty1 = ((foot_y + reach) / size).floor() as i32;
+
+        for ty in ty0..=ty1 {
+            let trect = tile_rect(tx, ty, size);
+            let surface = match map.tile(tx, ty) {
+                TileKind::Solid if on_slope => trect.y,
+                kind => match kind.floor((foot_x - trect.x) / size) {
+                    Some(h) => trect.y + trect.h - h * size,
+                    None => continue
+                }
+            };
+
+            if surface < bottom - step || surface > foot_y + reach + TOLERANCE {
+                continue;
+            }
+
+            // Highest surface under the foot wins
+            if ground.map_or(true, |(g, _)| surface < g) {
+                ground = Some((surface, trect));
+            }
+        }
+
+        if let Some((surface, trect)) = ground {
+            let dist = surface - bottom;
+            if y_delta.map_or(true, |d| dist < d) {
+                y_delta = Some(dist);
+                y_coll = Some(trect);
+            }
+        }
+    }
+
+    (x_delta, x_coll, y_delta, y_coll)
+}
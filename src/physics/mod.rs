@@ -0,0 +1,1052 @@
+pub mod map;
+pub mod event;
+pub mod broadphase;
+pub mod query;
+pub mod shape;
+pub mod debug;
+
+pub use self::map::*;
+pub use self::event::{CollisionEvent, ContactState};
+pub use self::broadphase::SpatialGrid;
+pub use self::query::RayHit;
+pub use self::shape::Shape;
+pub use self::debug::DebugShape;
+
+use std::ops::{Add, Sub};
+use std::time::Instant;
+
+use crate::rect::{Rect, IntoF32};
+use crate::fixed::Fixed;
+use crate::world::{World, Entity};
+use crate::query::query;
+use crate::events::EventBus;
+use crate::physics::event::{Contacts, overlap_normal};
+use crate::component::{
+    PositionComponent as Position,
+    VelocityComponent as Velocity,
+    PhysicsComponent as Physics,
+    BodyComponent as Body,
+};
+
+/// Distance between the bottom of an entity and the top of a platform for it to be carried
+const CARRY_TOLERANCE: f32 = 0.01;
+
+/// Gap between two solid entities for them to still be touching, so resting contacts persist
+/// while neither moves into the other
+const TOUCH_TOLERANCE: f32 = 0.01;
+
+/// Body list of the systems that run without body components
+struct NoBody;
+
+impl Body for NoBody {
+    fn on_ground(&self) -> bool { false }
+    fn on_wall(&self) -> bool { false }
+    fn set_on_ground(&mut self, _: bool) {}
+    fn set_on_wall(&mut self, _: bool) {}
+}
+
+/// How an entity takes part in collision resolution
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BodyType {
+    /// Never moves, even with a velocity
+    Static,
+    /// Moves by its velocity without being blocked, pushing and carrying dynamic bodies
+    Kinematic,
+    /// Moves by its velocity and is blocked by everything else
+    Dynamic,
+}
+
+/// Whether two hitboxes should collide based on their categories and masks
+pub fn interacts<A: Physics, B: Physics>(a: &A, b: &B) -> bool {
+    a.category() & b.mask() != 0 && b.category() & a.mask() != 0
+}
+
+/// Hash of the positions and velocities of every entity, for checking that two simulations
+/// are in sync. Absent entities are part of the hash as well.
+pub fn state_hash<P: Position, V: Velocity>(p: &Vec<Option<P>>, v: &Vec<Option<V>>) -> u64 {
+    // FNV-1a over the bit patterns, floats are compared exactly
+    let mut hash: u64 = 0xcbf29ce484222325;
+    let mut feed = |bits: u32| {
+        for byte in bits.to_le_bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    };
+
+    for i in 0..p.len().max(v.len()) {
+        feed(i as u32);
+
+        match p.get(i).and_then(|p| p.as_ref()) {
+            Some(pos) => { feed(1); feed(pos.x().to_bits()); feed(pos.y().to_bits()); }
+            None => feed(0),
+        }
+
+        match v.get(i).and_then(|v| v.as_ref()) {
+            Some(vel) => { feed(1); feed(vel.x().to_bits()); feed(vel.y().to_bits()); }
+            None => feed(0),
+        }
+    }
+
+    hash
+}
+
+/// How much time passes each tick
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Timestep {
+    /// The time measured since the last tick
+    RealTime,
+    /// The same number of seconds every tick, however long it actually took
+    Fixed(f32),
+}
+
+/// Default size of the broad phase cells
+const GRID_CELL: f32 = 64.0;
+
+/// Default distance overlapping entities are pushed apart by each tick
+const MAX_CORRECTION: f32 = 4.0;
+
+pub struct PhysicsSystem {
+    last_tick: Instant,
+    timestep: Timestep,
+    snap: bool,
+    max_correction: f32,
+    gravity: (f32, f32),
+    grid: SpatialGrid,
+    contacts: Contacts,
+    /// Entity at every index while running over a `World`, empty otherwise
+    entities: Vec<Option<Entity>>,
+    /// Broad phase results, kept between ticks so resolving doesn't allocate for every entity
+    candidates: Vec<usize>,
+    events: Vec<CollisionEvent>,
+    debug: Option<Vec<DebugShape>>,
+}
+
+impl PhysicsSystem {
+    pub fn new() -> PhysicsSystem {
+        PhysicsSystem {
+            last_tick: Instant::now(),
+            timestep: Timestep::RealTime,
+            snap: false,
+            max_correction: MAX_CORRECTION,
+            gravity: (0.0, 0.0),
+            grid: SpatialGrid::new(GRID_CELL),
+            contacts: Contacts::default(),
+            entities: Vec::new(),
+            candidates: Vec::new(),
+            events: Vec::new(),
+            debug: None,
+        }
+    }
+
+    /// Gravity applied to every entity with a body component
+    pub fn set_gravity(&mut self, x: f32, y: f32) {
+        self.gravity = (x, y);
+    }
+
+    /// Size of the broad phase cells, ideally a bit larger than most hitboxes
+    pub fn set_grid_cell(&mut self, size: f32) {
+        self.grid = SpatialGrid::new(size);
+    }
+
+    pub fn set_timestep(&mut self, timestep: Timestep) {
+        self.timestep = timestep;
+    }
+
+    /// Round positions and velocities to `Fixed` precision after every tick, so rounding
+    /// differences can never build up between two simulations. Box against box collisions
+    /// are worked out in `Fixed` as well.
+    pub fn set_snap(&mut self, snap: bool) {
+        self.snap = snap;
+    }
+
+    /// Step by exactly `dt` every tick and snap the results, so the same inputs always give
+    /// the same state. Entities are always processed in index order and contacts are sorted,
+    /// so nothing else depends on the machine running the simulation.
+    pub fn set_deterministic(&mut self, dt: f32) {
+        self.set_timestep(Timestep::Fixed(dt));
+        self.set_snap(true);
+    }
+
+    /// Furthest entities that overlap get pushed apart in a single tick, so ones spawned
+    /// inside each other separate over a few ticks instead of jumping
+    pub fn set_max_correction(&mut self, distance: f32) {
+        self.max_correction = distance;
+    }
+
+    /// Record hitboxes, sweeps and contacts every tick so they can be drawn
+    pub fn set_debug(&mut self, debug: bool) {
+        self.debug = if debug { Some(Vec::new()) } else { None };
+    }
+
+    /// What the last tick worked with, empty unless debugging is on
+    pub fn debug_shapes(&self) -> &[DebugShape] {
+        self.debug.as_deref().unwrap_or(&[])
+    }
+
+    /// Collision events produced by the last tick
+    pub fn events(&self) -> &[CollisionEvent] {
+        &self.events
+    }
+
+    /// Take the collision events produced by the last tick
+    pub fn drain_events(&mut self) -> std::vec::Drain<'_, CollisionEvent> {
+        self.events.drain(..)
+    }
+
+    /// Send the collision events produced by the last tick to an event bus
+    pub fn publish(&mut self, bus: &mut EventBus) {
+        bus.events_mut::<CollisionEvent>().extend(self.events.drain(..));
+    }
+
+    /// Move entities by their velocity without resolving collisions. Only positions are
+    /// snapped, see `velocity_bodies` to skip static bodies and snap velocities as well.
+    pub fn velocity<P: Position, V: Velocity>(
+        &mut self,
+        p: &mut Vec<Option<P>>,
+        v: &Vec<Option<V>>
+    ) {
+        let t = self.tick();
+        Self::apply_velocity(p, v, |_| true, t);
+        self.snap(p, &mut Vec::<Option<V>>::new());
+    }
+
+    /// Like `velocity`, but static bodies stay put. Entities without a physics component
+    /// move like dynamic ones.
+    pub fn velocity_bodies<P: Position, V: Velocity, PH: Physics>(
+        &mut self,
+        p: &mut Vec<Option<P>>,
+        v: &mut Vec<Option<V>>,
+        ph: &Vec<Option<PH>>
+    ) {
+        let t = self.tick();
+        Self::apply_velocity(p, v, |i| body_type(ph, i) != BodyType::Static, t);
+        self.snap(p, v);
+    }
+
+    pub fn collision<P, V, PH>(
+        &mut self,
+        p: &mut Vec<Option<P>>,
+        v: &mut Vec<Option<V>>,
+        ph: &mut Vec<Option<PH>>
+    )
+    where
+        P: Position,
+        V: Velocity,
+        PH: Physics,
+    {
+        let t = self.tick();
+        let map = Dummy{};
+        self.resolve(p, v, ph, &mut Vec::<Option<NoBody>>::new(), Some(&map), t);
+    }
+
+
+    pub fn collision_map<P, V, PH, CM>(
+        &mut self,
+        p: &mut Vec<Option<P>>,
+        v: &mut Vec<Option<V>>,
+        ph: &mut Vec<Option<PH>>,
+        map: Option<&CM>
+        )
+    where
+        P: Position,
+        V: Velocity,
+        PH: Physics,
+        CM: CollisionMap,
+    {
+        let t = self.tick();
+        self.resolve(p, v, ph, &mut Vec::<Option<NoBody>>::new(), map, t);
+    }
+
+    /// Integrate gravity and the body properties of each entity before resolving collisions.
+    /// Entities without a body component move as they do in `collision_map`.
+    pub fn simulate<P, V, PH, B, CM>(
+        &mut self,
+        p: &mut Vec<Option<P>>,
+        v: &mut Vec<Option<V>>,
+        ph: &mut Vec<Option<PH>>,
+        b: &mut Vec<Option<B>>,
+        map: Option<&CM>
+    )
+    where
+        P: Position,
+        V: Velocity,
+        PH: Physics,
+        B: Body,
+        CM: CollisionMap,
+    {
+        let t = self.tick();
+        Self::apply_forces(v, ph, b, self.gravity, t);
+        self.resolve(p, v, ph, b, map, t);
+    }
+
+    /// `velocity` over the components of a world
+    pub fn velocity_world<P, V>(&mut self, world: &World)
+    where
+        P: Position + 'static,
+        V: Velocity + 'static,
+    {
+        self.velocity(&mut world.borrow_mut::<P>(), &world.borrow::<V>());
+    }
+
+    /// `velocity_bodies` over the components of a world
+    pub fn velocity_bodies_world<P, V, PH>(&mut self, world: &World)
+    where
+        P: Position + 'static,
+        V: Velocity + 'static,
+        PH: Physics + 'static,
+    {
+        self.velocity_bodies(&mut world.borrow_mut::<P>(), &mut world.borrow_mut::<V>(), &world.borrow::<PH>());
+    }
+
+    /// `collision` over the components of a world
+    pub fn collision_world<P, V, PH>(&mut self, world: &World)
+    where
+        P: Position + 'static,
+        V: Velocity + 'static,
+        PH: Physics + 'static,
+    {
+        self.track(world);
+        self.collision(&mut world.borrow_mut::<P>(), &mut world.borrow_mut::<V>(), &mut world.borrow_mut::<PH>());
+        self.entities.clear();
+    }
+
+    /// `collision_map` over the components of a world
+    pub fn collision_map_world<P, V, PH, CM>(&mut self, world: &World, map: Option<&CM>)
+    where
+        P: Position + 'static,
+        V: Velocity + 'static,
+        PH: Physics + 'static,
+        CM: CollisionMap,
+    {
+        self.track(world);
+        self.collision_map(&mut world.borrow_mut::<P>(), &mut world.borrow_mut::<V>(), &mut world.borrow_mut::<PH>(), map);
+        self.entities.clear();
+    }
+
+    /// `simulate` over the components of a world
+    pub fn simulate_world<P, V, PH, B, CM>(&mut self, world: &World, map: Option<&CM>)
+    where
+        P: Position + 'static,
+        V: Velocity + 'static,
+        PH: Physics + 'static,
+        B: Body + 'static,
+        CM: CollisionMap,
+    {
+        self.track(world);
+        self.simulate(
+            &mut world.borrow_mut::<P>(),
+            &mut world.borrow_mut::<V>(),
+            &mut world.borrow_mut::<PH>(),
+            &mut world.borrow_mut::<B>(),
+            map
+        );
+        self.entities.clear();
+    }
+
+    /// Remember which entity lives at every index of a world, so the events of the coming
+    /// tick carry their ids
+    fn track(&mut self, world: &World) {
+        self.entities.clear();
+        self.entities.extend((0..world.slots()).map(|i| world.entity(i)));
+    }
+
+    /// Run a full collision tick and emit the contact events it produced
+    fn resolve<P, V, PH, B, CM>(
+        &mut self,
+        p: &mut Vec<Option<P>>,
+        v: &mut Vec<Option<V>>,
+        ph: &mut Vec<Option<PH>>,
+        b: &mut Vec<Option<B>>,
+        map: Option<&CM>,
+        t: f32,
+    )
+    where
+        P: Position,
+        V: Velocity,
+        PH: Physics,
+        B: Body,
+        CM: CollisionMap,
+    {
+        self.events.clear();
+
+        // Entities are bucketed by everything they could sweep through this tick first, then
+        // by where they ended up for the sensor pass and queries until the next tick
+        // Kinematic bodies move first so everything else resolves against where they end up
+        Self::fill_grid(&mut self.grid, p, v, ph, t);
+        Self::move_kinematic(p, v, ph, &self.grid, &mut self.contacts, t);
+
+        Self::fill_grid(&mut self.grid, p, v, ph, t);
+        Self::separate(p, v, ph, b, &self.grid, &mut self.contacts, self.max_correction);
+        if let Some(debug) = self.debug.as_mut() {
+            debug.clear();
+            Self::record_sweeps(debug, p, v, ph, t);
+        }
+        Self::apply_collision(p, v, ph, b, map, &self.grid, &mut self.contacts, &mut self.candidates, self.snap, t);
+        Self::apply_velocity(p, v, |i| body_type(ph, i) == BodyType::Dynamic, t);
+        Self::fill_grid(&mut self.grid, p, v, ph, 0.0);
+        Self::detect_contacts(p, ph, &self.grid, &mut self.contacts);
+        self.contacts.finish(&mut self.events, &self.entities);
+        self.snap(p, v);
+
+        if let Some(debug) = self.debug.as_mut() {
+            Self::record_hitboxes(debug, p, ph);
+            Self::record_contacts(debug, p, ph, &self.events);
+        }
+    }
+
+    fn record_sweeps<P, V, PH>(
+        debug: &mut Vec<DebugShape>,
+        p: &Vec<Option<P>>,
+        v: &Vec<Option<V>>,
+        ph: &Vec<Option<PH>>,
+        t: f32,
+    )
+    where
+        P: Position,
+        V: Velocity,
+        PH: Physics,
+    {
+        for i in 0..p.len() {
+            if p[i].is_none() || v[i].is_none() || ph[i].is_none() { continue; }
+
+            let phy = ph[i].as_ref().unwrap();
+            if phy.body_type() != BodyType::Dynamic || phy.is_sensor() { continue; }
+
+            let (after_x, after_y) = axis_sweeps(&phy.hitbox().after_position(p[i].as_ref().unwrap()), v[i].as_ref().unwrap(), t);
+            debug.push(DebugShape::Sweep(after_x));
+            debug.push(DebugShape::Sweep(after_y));
+        }
+    }
+
+    fn record_hitboxes<P: Position, PH: Physics>(
+        debug: &mut Vec<DebugShape>,
+        p: &Vec<Option<P>>,
+        ph: &Vec<Option<PH>>,
+    ) {
+        for i in 0..p.len() {
+            if p[i].is_none() || ph[i].is_none() { continue; }
+            debug.push(DebugShape::Hitbox(ph[i].as_ref().unwrap().shape().after_position(p[i].as_ref().unwrap())));
+        }
+    }
+
+    fn record_contacts<P: Position, PH: Physics>(
+        debug: &mut Vec<DebugShape>,
+        p: &Vec<Option<P>>,
+        ph: &Vec<Option<PH>>,
+        events: &[CollisionEvent],
+    ) {
+        let bounds = |i: usize| ph[i].as_ref().unwrap().shape().after_position(p[i].as_ref().unwrap()).bounds();
+
+        for e in events.iter().filter(|e| e.state != ContactState::Ended) {
+            if p[e.a].is_none() || ph[e.a].is_none() || p[e.b].is_none() || ph[e.b].is_none() { continue; }
+
+            // Middle of where the bounds meet
+            let (a, b) = (bounds(e.a), bounds(e.b));
+            let left = a.x.max(b.x);
+            let right = (a.x + a.w).min(b.x + b.w);
+            let top = a.y.max(b.y);
+            let bottom = (a.y + a.h).min(b.y + b.h);
+
+            debug.push(DebugShape::Contact {
+                point: ((left + right) / 2.0, (top + bottom) / 2.0),
+                normal: e.normal,
+            });
+        }
+    }
+
+    /// Seconds to simulate this tick
+    fn tick(&mut self) -> f32 {
+        let elapsed = self.last_tick.elapsed().as_secs_f32();
+        self.last_tick = Instant::now();
+
+        match self.timestep {
+            Timestep::RealTime => elapsed,
+            Timestep::Fixed(dt) => dt,
+        }
+    }
+
+    fn snap<P: Position, V: Velocity>(&self, p: &mut Vec<Option<P>>, v: &mut Vec<Option<V>>) {
+        if !self.snap { return; }
+
+        for pos in p.iter_mut().flatten() {
+            pos.set_x(Fixed::snap(pos.x()));
+            pos.set_y(Fixed::snap(pos.y()));
+        }
+
+        for vel in v.iter_mut().flatten() {
+            vel.set_x(Fixed::snap(vel.x()));
+            vel.set_y(Fixed::snap(vel.y()));
+        }
+    }
+
+    fn fill_grid<P, V, PH>(
+        grid: &mut SpatialGrid,
+        p: &Vec<Option<P>>,
+        v: &Vec<Option<V>>,
+        ph: &Vec<Option<PH>>,
+        t: f32,
+    )
+    where
+        P: Position,
+        V: Velocity,
+        PH: Physics,
+    {
+        grid.clear();
+
+        for i in 0..p.len() {
+            if p[i].is_none() || ph[i].is_none() { continue; }
+
+            let mut bounds = ph[i].as_ref().unwrap().shape().after_position(p[i].as_ref().unwrap()).bounds();
+            if let Some(vel) = v.get(i).and_then(|v| v.as_ref()) {
+                bounds = swept(&bounds, vel.x() * t, vel.y() * t);
+            }
+
+            grid.insert(i, &bounds);
+        }
+    }
+
+    fn apply_velocity<P: Position, V: Velocity, F: Fn(usize) -> bool>(
+        p: &mut Vec<Option<P>>,
+        v: &Vec<Option<V>>,
+        moves: F,
+        t: f32,
+    ) {
+        for (i, (pos, vel)) in query((p, v)) {
+            if !moves(i) { continue; }
+
+            pos.set_x(pos.x() + vel.x() * t);
+            pos.set_y(pos.y() + vel.y() * t);
+        }
+    }
+
+    /// Semi-implicit Euler: velocities are updated here, positions afterwards from the new
+    /// velocities once collisions are resolved
+    fn apply_forces<V: Velocity, PH: Physics, B: Body>(
+        v: &mut Vec<Option<V>>,
+        ph: &Vec<Option<PH>>,
+        b: &Vec<Option<B>>,
+        gravity: (f32, f32),
+        t: f32,
+    ) {
+        for i in 0..v.len().min(b.len()) {
+            if v[i].is_none() || b[i].is_none() { continue; }
+            if body_type(ph, i) != BodyType::Dynamic { continue; }
+
+            let vel = v[i].as_mut().unwrap();
+            let body = b[i].as_ref().unwrap();
+            let (ax, ay) = body.acceleration();
+
+            let mut vx = vel.x() + (gravity.0 * body.gravity_scale() + ax) * t;
+            let mut vy = vel.y() + (gravity.1 * body.gravity_scale() + ay) * t;
+
+            // Damping never reverses the direction of travel
+            let damping = (1.0 - body.linear_damping() * t).max(0.0);
+            vx *= damping;
+            vy *= damping;
+
+            if body.on_ground() && ax == 0.0 {
+                let slowed = (vx.abs() - body.friction() * t).max(0.0);
+                vx = slowed.copysign(vx);
+            }
+
+            vel.set_x(vx.clamp(-body.max_speed(), body.max_speed()));
+            vel.set_y(vy.min(body.terminal_velocity()));
+        }
+    }
+
+    /// Move kinematic bodies by their velocity, carrying the dynamic bodies standing on top
+    /// of them and pushing away the ones they run into
+    fn move_kinematic<P, V, PH>(
+        p: &mut Vec<Option<P>>,
+        v: &mut Vec<Option<V>>,
+        ph: &Vec<Option<PH>>,
+        grid: &SpatialGrid,
+        contacts: &mut Contacts,
+        t: f32,
+    )
+    where
+        P: Position,
+        V: Velocity,
+        PH: Physics,
+    {
+        let mut candidates = Vec::new();
+        let mut carried = vec![false; p.len()];
+
+        for k in 0..p.len() {
+            if p[k].is_none() || v[k].is_none() || ph[k].is_none() { continue; }
+
+            let kphy = ph[k].as_ref().unwrap();
+            if kphy.body_type() != BodyType::Kinematic { continue; }
+
+            let vel = v[k].as_ref().unwrap();
+            let (dx, dy) = (vel.x() * t, vel.y() * t);
+            let before = kphy.hitbox().after_position(p[k].as_ref().unwrap());
+
+            let pos = p[k].as_mut().unwrap();
+            pos.set_x(pos.x() + dx);
+            pos.set_y(pos.y() + dy);
+            let after = kphy.hitbox().after_position(p[k].as_ref().unwrap());
+
+            grid.query(&swept(&before, dx, dy), &mut candidates);
+            for &d in candidates.iter() {
+                if d == k || p[d].is_none() || v.get(d).map_or(true, |v| v.is_none()) { continue; }
+
+                let dphy = match ph[d].as_ref() {
+                    Some(dphy) => dphy,
+                    None => continue
+                };
+                if dphy.body_type() != BodyType::Dynamic || dphy.is_sensor() { continue; }
+                if kphy.is_sensor() || !interacts(kphy, dphy) { continue; }
+
+                let drect = dphy.hitbox().after_position(p[d].as_ref().unwrap());
+
+                // Riders standing on the platform move along with it
+                let riding = (drect.y + drect.h - before.y).abs() < CARRY_TOLERANCE &&
+                    drect.x < before.x + before.w && drect.x + drect.w > before.x &&
+                    v[d].as_ref().unwrap().y() >= 0.0;
+
+                if riding && !carried[d] {
+                    carried[d] = true;
+                    let pos = p[d].as_mut().unwrap();
+                    pos.set_x(pos.x() + dx);
+                    pos.set_y(pos.y() + dy);
+                    contacts.insert(d, k, (0.0, -1.0));
+                    continue;
+                }
+
+                // Anything else in the way is pushed out along the shallowest axis
+                if drect.has_intersection(&after) {
+                    let normal = overlap_normal(&drect, &after);
+                    let depth = if normal.0 != 0.0 {
+                        (drect.w + after.w) / 2.0 - ((drect.x + drect.w / 2.0) - (after.x + after.w / 2.0)).abs()
+                    } else {
+                        (drect.h + after.h) / 2.0 - ((drect.y + drect.h / 2.0) - (after.y + after.h / 2.0)).abs()
+                    };
+
+                    let pos = p[d].as_mut().unwrap();
+                    pos.set_x(pos.x() + normal.0 * depth);
+                    pos.set_y(pos.y() + normal.1 * depth);
+
+                    // Stop moving into the platform
+                    let dvel = v[d].as_mut().unwrap();
+                    if dvel.x() * normal.0 < 0.0 { dvel.set_x(0.0); }
+                    if dvel.y() * normal.1 < 0.0 { dvel.set_y(0.0); }
+
+                    contacts.insert(d, k, normal);
+                }
+            }
+        }
+    }
+
+    /// Push overlapping entities apart along the minimum translation vector. Dynamic bodies
+    /// share the correction by mass, everything else stays put.
+    fn separate<P, V, PH, B>(
+        p: &mut Vec<Option<P>>,
+        v: &mut Vec<Option<V>>,
+        ph: &Vec<Option<PH>>,
+        b: &Vec<Option<B>>,
+        grid: &SpatialGrid,
+        contacts: &mut Contacts,
+        max_correction: f32,
+    )
+    where
+        P: Position,
+        V: Velocity,
+        PH: Physics,
+        B: Body,
+    {
+        // Share of the correction each entity takes, zero for the ones that never move
+        let weights: Vec<f32> = (0..p.len()).map(|i| {
+            if p[i].is_none() || v.get(i).map_or(true, |v| v.is_none()) { return 0.0; }
+            if body_type(ph, i) != BodyType::Dynamic { return 0.0; }
+
+            // Massless or broken bodies are treated as immovable rather than infinitely light
+            let mass = b.get(i).and_then(|b| b.as_ref()).map_or(1.0, |b| b.mass());
+            if mass > 0.0 && mass.is_finite() { 1.0 / mass } else { 0.0 }
+        }).collect();
+
+        let mut candidates = Vec::new();
+
+        for i in 0..p.len() {
+            if p[i].is_none() || ph[i].is_none() { continue; }
+
+            let iphy = ph[i].as_ref().unwrap();
+            if iphy.is_sensor() { continue; }
+
+            let ishape = iphy.shape().after_position(p[i].as_ref().unwrap());
+            grid.query(&ishape.bounds(), &mut candidates);
+
+            for &j in candidates.iter().filter(|&&j| j > i) {
+                if p[j].is_none() || ph[j].is_none() { continue; }
+
+                let jphy = ph[j].as_ref().unwrap();
+                if jphy.is_sensor() || !interacts(iphy, jphy) { continue; }
+
+                let (wi, wj) = (weights[i], weights[j]);
+                if wi + wj == 0.0 { continue; }
+
+                // Positions of earlier pairs may have moved i already
+                let ishape = iphy.shape().after_position(p[i].as_ref().unwrap());
+                let jshape = jphy.shape().after_position(p[j].as_ref().unwrap());
+                let (normal, depth) = match ishape.penetration(&jshape) {
+                    Some(mtv) => mtv,
+                    None => continue
+                };
+
+                let depth = depth.min(max_correction);
+                for (k, share, n) in [(i, wi, normal), (j, wj, (-normal.0, -normal.1))] {
+                    if share == 0.0 { continue; }
+
+                    let amount = depth * share / (wi + wj);
+                    let pos = p[k].as_mut().unwrap();
+                    pos.set_x(pos.x() + n.0 * amount);
+                    pos.set_y(pos.y() + n.1 * amount);
+
+                    // Stop sinking further in while still overlapping
+                    bounce(v[k].as_mut().unwrap(), n, 0.0);
+                }
+
+                contacts.insert(i, j, normal);
+            }
+        }
+    }
+
+    fn apply_collision<P, V, PH, B, CM>(
+        p: &mut Vec<Option<P>>,
+        v: &mut Vec<Option<V>>,
+        ph: &mut Vec<Option<PH>>,
+        b: &mut Vec<Option<B>>,
+        map: Option<&CM>,
+        grid: &SpatialGrid,
+        contacts: &mut Contacts,
+        candidates: &mut Vec<usize>,
+        fixed: bool,
+        t: f32,
+    )
+    where
+        P: Position,
+        V: Velocity,
+        PH: Physics,
+        B: Body,
+        CM: CollisionMap,
+    {
+        // Other entities are looked up while resolving, so only the indices are taken up front
+        let movers: Vec<usize> = query((&*p, &*v, &*ph))
+            .filter(|(_, (_, _, phy))| phy.body_type() == BodyType::Dynamic)
+            .map(|(i, _)| i)
+            .collect();
+
+        for i in movers {
+            let vel = v[i].as_mut().unwrap();
+            let (vx, vy) = (vel.x(), vel.y());
+
+            let irect = ph[i].as_ref().unwrap().hitbox().after_position(p[i].as_ref().unwrap());
+            let (mut after_x, mut after_y) = axis_sweeps(&irect, vel, t);
+
+            let (x_hit, y_hit) = Self::handle_collision(p, ph, map, grid, contacts, candidates, i, vel, &irect, &mut after_x, &mut after_y, fixed, t);
+
+            // Any collision on an axis stops the entity on it, or bounces it back
+            let restitution = ph[i].as_ref().unwrap().restitution();
+            let reflect = ph[i].as_ref().unwrap().reflect();
+            if let Some(normal) = x_hit.normal {
+                bounce(vel, if reflect { normal } else { (against(vx), 0.0) }, restitution);
+            }
+            if let Some(normal) = y_hit.normal {
+                bounce(vel, if reflect { normal } else { (0.0, against(vy)) }, restitution);
+            }
+
+            let pos = p[i].as_mut().unwrap();
+            let phy = ph[i].as_mut().unwrap();
+            pos.set_x(pos.x() + x_hit.delta.unwrap_or(0.0));
+            pos.set_y(pos.y() + y_hit.delta.unwrap_or(0.0));
+            phy.set_x_collision(x_hit.with);
+            phy.set_y_collision(y_hit.with);
+
+            if let Some(body) = b.get_mut(i).and_then(|b| b.as_mut()) {
+                body.set_on_ground(vy > 0.0 && y_hit.normal.is_some());
+                body.set_on_wall(vx != 0.0 && x_hit.normal.is_some());
+            }
+        }
+    }
+
+    fn handle_collision<P, V, PH, CM>(
+        p: &Vec<Option<P>>,
+        ph: &Vec<Option<PH>>,
+        map: Option<&CM>,
+        grid: &SpatialGrid,
+        contacts: &mut Contacts,
+        candidates: &mut Vec<usize>,
+        i: usize,
+        vel: &V,
+        irect: &Rect<f32>,
+        after_x: &mut Rect<f32>,
+        after_y: &mut Rect<f32>,
+        fixed: bool,
+        t: f32,
+    ) -> (AxisHit, AxisHit)
+    where
+        P: Position,
+        V: Velocity,
+        PH: Physics,
+        CM: CollisionMap,
+    {
+        let mut x_hit = AxisHit::default();
+        let mut y_hit = AxisHit::default();
+        let (vx, vy) = (vel.x(), vel.y());
+
+        let iphy = ph[i].as_ref().unwrap();
+
+        // Sensors only report overlaps, they neither block nor get blocked
+        if iphy.is_sensor() {
+            return (x_hit, y_hit);
+        }
+
+        // Check map collisions if applicable
+        if let Some(map) = map.filter(|m| iphy.mask() & m.category() != 0) {
+            if let Some(size) = map.tile_size() {
+                // Tile maps know the kind of each cell so the exact distance can be resolved
+                let (x_delta, x_coll, y_delta, y_coll) = map::resolve_tiles(
+                    map, size, irect, after_x, after_y, (vx, vy), t
+                );
+
+                if x_delta.is_some() {
+                    x_hit = AxisHit { delta: x_delta, with: x_coll, normal: Some((against(vx), 0.0)), entity: None };
+                }
+
+                if y_delta.is_some() {
+                    y_hit = AxisHit { delta: y_delta, with: y_coll, normal: Some((0.0, against(vy))), entity: None };
+                }
+            } else {
+                // For each axis do a binary search sort of thing to figure out
+                // how much the rect is allowed to move
+                if map.test(after_x) {
+                    x_hit.normal = Some((against(vx), 0.0));
+                }
+
+                if map.test(after_y) {
+                    y_hit.normal = Some((0.0, against(vy)));
+                }
+            }
+        }
+
+        // For every other entity near the sweep, check whether the new hitbox after x and y
+        // components of the velocity intersects
+        grid.query(&swept(irect, vx * t, vy * t), candidates);
+
+        for &j in candidates.iter() {
+            if i==j || p[j].is_none() || ph[j].is_none() { continue; }
+            if ph[j].as_ref().unwrap().is_sensor() { continue; }
+            if !interacts(iphy, ph[j].as_ref().unwrap()) { continue; }
+
+            let ishape = iphy.shape().after_position(p[i].as_ref().unwrap());
+            let jshape = ph[j].as_ref().unwrap().shape().after_position(p[j].as_ref().unwrap());
+
+            // Still overlapping after `separate`: it may slide along or move out, but not in
+            if ishape.overlaps(&jshape) {
+                let normal = match (ishape, jshape) {
+                    (Shape::Aabb(irect), Shape::Aabb(jrect)) => overlap_normal(&irect, &jrect),
+                    _ => ishape.penetration(&jshape).map_or_else(|| ishape.normal(&jshape), |(n, _)| n),
+                };
+
+                if vx * normal.0 < 0.0 {
+                    x_hit.closer(j, 0.0, jshape.bounds(), normal);
+                }
+                if vy * normal.1 < 0.0 {
+                    y_hit.closer(j, 0.0, jshape.bounds(), normal);
+                }
+
+                continue;
+            }
+
+            // Anything but two boxes is swept exactly along each axis
+            if !matches!((ishape, jshape), (Shape::Aabb(_), Shape::Aabb(_))) {
+                if let Some(f) = ishape.sweep(&jshape, vx * t, 0.0) {
+                    let dist = vx * t * f;
+                    let normal = ishape.translate(dist, 0.0).normal(&jshape);
+                    x_hit.closer(j, dist, jshape.bounds(), normal);
+                }
+
+                if let Some(f) = ishape.sweep(&jshape, 0.0, vy * t) {
+                    let dist = vy * t * f;
+                    let normal = ishape.translate(0.0, dist).normal(&jshape);
+                    y_hit.closer(j, dist, jshape.bounds(), normal);
+                }
+
+                continue;
+            }
+
+            let jrect = jshape.bounds();
+
+            // Deterministic runs compare boxes in fixed point, so float rounding on different
+            // machines can't decide whether they touch
+            let (hits_x, hits_y, dist_x, dist_y) = if fixed {
+                let (ifixed, jfixed) = (irect.to_fixed(), jrect.to_fixed());
+                (
+                    jfixed.has_intersection(&after_x.to_fixed()),
+                    jfixed.has_intersection(&after_y.to_fixed()),
+                    gap_x(&ifixed, &jfixed, vx >= 0.0).to_f32(),
+                    gap_y(&ifixed, &jfixed, vy >= 0.0).to_f32(),
+                )
+            } else {
+                (
+                    jrect.has_intersection(after_x),
+                    jrect.has_intersection(after_y),
+                    gap_x(irect, &jrect, vx >= 0.0),
+                    gap_y(irect, &jrect, vy >= 0.0),
+                )
+            };
+
+            // Store smallest distance (magnitude)
+            if hits_x {
+                x_hit.closer(j, dist_x, jrect, (against(vx), 0.0));
+            }
+
+            if hits_y {
+                y_hit.closer(j, dist_y, jrect, (0.0, against(vy)));
+            }
+        }
+
+        // Only what actually stopped the entity touches it, not everything behind that
+        for hit in [&x_hit, &y_hit] {
+            if let (Some(j), Some(normal)) = (hit.entity, hit.normal) {
+                contacts.insert(i, j, normal);
+            }
+        }
+
+        (x_hit, y_hit)
+    }
+
+    /// Record every overlap involving a sensor as a contact, along with solid entities resting
+    /// against each other
+    fn detect_contacts<P, PH>(
+        p: &Vec<Option<P>>,
+        ph: &Vec<Option<PH>>,
+        grid: &SpatialGrid,
+        contacts: &mut Contacts,
+    )
+    where
+        P: Position,
+        PH: Physics,
+    {
+        let mut candidates = Vec::new();
+
+        for i in 0..p.len() {
+            if p[i].is_none() || ph[i].is_none() { continue; }
+
+            let iphy = ph[i].as_ref().unwrap();
+            let ishape = iphy.shape().after_position(p[i].as_ref().unwrap());
+            let grown = ishape.grow(TOUCH_TOLERANCE);
+            grid.query(&grown.bounds(), &mut candidates);
+
+            for &j in candidates.iter().filter(|&&j| j > i) {
+                if p[j].is_none() || ph[j].is_none() { continue; }
+
+                let jphy = ph[j].as_ref().unwrap();
+                if !interacts(iphy, jphy) { continue; }
+
+                let jshape = jphy.shape().after_position(p[j].as_ref().unwrap());
+
+                if iphy.is_sensor() || jphy.is_sensor() {
+                    if !ishape.overlaps(&jshape) { continue; }
+
+                    let normal = match (ishape, jshape) {
+                        (Shape::Aabb(irect), Shape::Aabb(jrect)) => overlap_normal(&irect, &jrect),
+                        _ => ishape.normal(&jshape),
+                    };
+                    contacts.insert(i, j, normal);
+                } else {
+                    if !grown.overlaps(&jshape) { continue; }
+
+                    let normal = match (grown, jshape) {
+                        (Shape::Aabb(irect), Shape::Aabb(jrect)) => overlap_normal(&irect, &jrect),
+                        _ => ishape.normal(&jshape),
+                    };
+                    contacts.touch(i, j, normal);
+                }
+            }
+        }
+    }
+}
+
+/// How an entity got blocked along one axis of its movement
+#[derive(Debug, Default)]
+struct AxisHit {
+    /// Distance it can still move before touching
+    delta: Option<f32>,
+    /// Hitbox of what it touched
+    with: Option<Rect<f32>>,
+    /// Normal of the surface that blocked it, pointing back at the entity
+    normal: Option<(f32, f32)>,
+    /// Index of the entity that blocked it, `None` for the map
+    entity: Option<usize>,
+}
+
+impl AxisHit {
+    /// Keep the hit only if it is closer than the current one
+    fn closer(&mut self, entity: usize, delta: f32, with: Rect<f32>, normal: (f32, f32)) {
+        if self.delta.map_or(true, |d| delta.abs() < d.abs()) {
+            self.delta = Some(delta);
+            self.with = Some(with);
+            self.normal = Some(normal);
+            self.entity = Some(entity);
+        }
+    }
+}
+
+/// Distance from the edge of `a` facing the movement to the facing edge of `b` along x
+fn gap_x<T: Copy + Add<Output = T> + Sub<Output = T>>(a: &Rect<T>, b: &Rect<T>, right: bool) -> T {
+    if right { b.x - (a.x + a.w) } else { (b.x + b.w) - a.x }
+}
+
+/// Distance from the edge of `a` facing the movement to the facing edge of `b` along y
+fn gap_y<T: Copy + Add<Output = T> + Sub<Output = T>>(a: &Rect<T>, b: &Rect<T>, down: bool) -> T {
+    if down { b.y - (a.y + a.h) } else { (b.y + b.h) - a.y }
+}
+
+/// Normal of a surface blocking movement with velocity `v` along one axis
+fn against(v: f32) -> f32 {
+    if v >= 0.0 { -1.0 } else { 1.0 }
+}
+
+/// Remove the part of the velocity going into the surface and send `restitution` of it back
+fn bounce<V: Velocity>(vel: &mut V, normal: (f32, f32), restitution: f32) {
+    let dot = vel.x() * normal.0 + vel.y() * normal.1;
+    if dot >= 0.0 { return; }
+
+    vel.set_x(vel.x() - (1.0 + restitution) * dot * normal.0);
+    vel.set_y(vel.y() - (1.0 + restitution) * dot * normal.1);
+}
+
+/// Body type of an entity, entities without a physics component are dynamic
+fn body_type<PH: Physics>(ph: &Vec<Option<PH>>, i: usize) -> BodyType {
+    ph.get(i).and_then(|phy| phy.as_ref()).map_or(BodyType::Dynamic, |phy| phy.body_type())
+}
+
+/// Rect stretched by the movement along each axis separately
+fn axis_sweeps<V: Velocity>(rect: &Rect<f32>, vel: &V, t: f32) -> (Rect<f32>, Rect<f32>) {
+    let mut after_x = *rect;
+    let mut after_y = *rect;
+
+    after_x.w += vel.x().abs() * t;
+    after_y.h += vel.y().abs() * t;
+
+    if vel.x() <= 0.0 {
+        after_x.x -= vel.x().abs() * t;
+    }
+
+    if vel.y() <= 0.0 {
+        after_y.y -= vel.y().abs() * t;
+    }
+
+    (after_x, after_y)
+}
+
+/// Bounds covering a rect before and after moving it
+fn swept(rect: &Rect<f32>, dx: f32, dy: f32) -> Rect<f32> {
+    Rect::new(
+        rect.x + dx.min(0.0),
+        rect.y + dy.min(0.0),
+        rect.w + dx.abs(),
+        rect.h + dy.abs()
+    )
+}
@@ -0,0 +1,580 @@
+use crate::rect::Rect;
+use crate::physics::{PhysicsSystem, CollisionMap, TileKind, Shape};
+use crate::physics::shape::closest_on_segment;
+use crate::component::{
+    PositionComponent as Position,
+    PhysicsComponent as Physics,
+};
+
+/// Step used to march through maps that only implement `CollisionMap::test`
+const MARCH_STEP: f32 = 1.0;
+const EPSILON: f32 = 0.001;
+
+/// Furthest rays and casts reach
+const MAX_MAP_DISTANCE: f32 = 65536.0;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RayHit {
+    /// Entity that was hit, `None` when the map was hit
+    pub entity: Option<usize>,
+    /// Point of impact. For box casts this is where the origin of the rect ends up.
+    pub point: (f32, f32),
+    /// Normal of the surface that was hit
+    pub normal: (f32, f32),
+    /// Distance travelled along the ray before the hit
+    pub distance: f32,
+}
+
+impl PhysicsSystem {
+    /// Cast a ray from `origin` along `dir` for at most `max` units and return the first thing
+    /// it hits. Only hitboxes in a category of `mask` are considered, sensors are skipped, and
+    /// hitboxes containing the origin are ignored so rays can be cast from inside an entity.
+    ///
+    /// Entities are looked up in the broad phase built during the last tick, so they are found
+    /// where that tick left them: entities spawned or moved since are a frame stale until the
+    /// next tick. Rays longer than 65536 units, `f32::INFINITY` included, are cut to that.
+    pub fn raycast<P, PH, CM>(
+        &self,
+        p: &Vec<Option<P>>,
+        ph: &Vec<Option<PH>>,
+        map: Option<&CM>,
+        origin: (f32, f32),
+        dir: (f32, f32),
+        max: f32,
+        mask: u32,
+    ) -> Option<RayHit>
+    where
+        P: Position,
+        PH: Physics,
+        CM: CollisionMap,
+    {
+        let max = max.min(MAX_MAP_DISTANCE);
+        let dir = normalize(dir)?;
+        let end = (origin.0 + dir.0 * max, origin.1 + dir.1 * max);
+        let bounds = Rect::new(
+            origin.0.min(end.0),
+            origin.1.min(end.1),
+            (end.0 - origin.0).abs(),
+            (end.1 - origin.1).abs()
+        );
+
+        let mut best = map
+            .filter(|m| m.category() & mask != 0)
+            .and_then(|m| ray_map(m, origin, dir, max));
+
+        let mut candidates = Vec::new();
+        self.grid.query(&bounds, &mut candidates);
+
+        for j in candidates {
+            if j >= p.len() || p[j].is_none() || ph[j].is_none() { continue; }
+
+            let phy = ph[j].as_ref().unwrap();
+            if phy.is_sensor() || phy.category() & mask == 0 { continue; }
+
+            let shape = phy.shape().after_position(p[j].as_ref().unwrap());
+            let limit = best.map_or(max, |b| b.distance);
+
+            if let Some((t, normal)) = ray_shape(origin, dir, &shape, limit) {
+                best = Some(RayHit {
+                    entity: Some(j),
+                    point: (origin.0 + dir.0 * t, origin.1 + dir.1 * t),
+                    normal,
+                    distance: t,
+                });
+            }
+        }
+
+        best
+    }
+
+    /// Cast a ray between two points, see `raycast`
+    pub fn segment<P, PH, CM>(
+        &self,
+        p: &Vec<Option<P>>,
+        ph: &Vec<Option<PH>>,
+        map: Option<&CM>,
+        from: (f32, f32),
+        to: (f32, f32),
+        mask: u32,
+    ) -> Option<RayHit>
+    where
+        P: Position,
+        PH: Physics,
+        CM: CollisionMap,
+    {
+        let d = (to.0 - from.0, to.1 - from.1);
+        self.raycast(p, ph, map, from, d, (d.0 * d.0 + d.1 * d.1).sqrt(), mask)
+    }
+
+    /// Sweep a rect along `dir` for at most `max` units and return the first thing it touches.
+    /// Hitboxes the rect already overlaps are ignored. Against slopes only the foot of the rect
+    /// (bottom center) is swept, matching how entities walk on them. Round shapes are treated
+    /// as their bounds. Like `raycast` this reads the broad phase of the last tick and cuts
+    /// `max` to 65536 units.
+    pub fn box_cast<P, PH, CM>(
+        &self,
+        p: &Vec<Option<P>>,
+        ph: &Vec<Option<PH>>,
+        map: Option<&CM>,
+        rect: &Rect<f32>,
+        dir: (f32, f32),
+        max: f32,
+        mask: u32,
+    ) -> Option<RayHit>
+    where
+        P: Position,
+        PH: Physics,
+        CM: CollisionMap,
+    {
+        let max = max.min(MAX_MAP_DISTANCE);
+        let dir = normalize(dir)?;
+        let origin = (rect.x, rect.y);
+        let mut bounds = *rect;
+        bounds.x += (dir.0 * max).min(0.0);
+        bounds.y += (dir.1 * max).min(0.0);
+        bounds.w += (dir.0 * max).abs();
+        bounds.h += (dir.1 * max).abs();
+
+        let mut best = map
+            .filter(|m| m.category() & mask != 0)
+            .and_then(|m| box_map(m, rect, dir, max));
+
+        let mut candidates = Vec::new();
+        self.grid.query(&bounds, &mut candidates);
+
+        for j in candidates {
+            if j >= p.len() || p[j].is_none() || ph[j].is_none() { continue; }
+
+            let phy = ph[j].as_ref().unwrap();
+            if phy.is_sensor() || phy.category() & mask == 0 { continue; }
+
+            // Sweeping the rect against a box is a ray from its origin against the box grown
+            // by the size of the rect
+            let jrect = phy.shape().after_position(p[j].as_ref().unwrap()).bounds();
+            let grown = Rect::new(jrect.x - rect.w, jrect.y - rect.h, jrect.w + rect.w, jrect.h + rect.h);
+            let limit = best.map_or(max, |b| b.distance);
+
+            if let Some((t, normal)) = ray_polygon(origin, dir, &rect_polygon(&grown), limit) {
+                best = Some(RayHit {
+                    entity: Some(j),
+                    point: (origin.0 + dir.0 * t, origin.1 + dir.1 * t),
+                    normal,
+                    distance: t,
+                });
+            }
+        }
+
+        best
+    }
+
+    /// Every entity whose shape overlaps `rect`, as of the last tick
+    pub fn query_rect<P, PH>(
+        &self,
+        p: &Vec<Option<P>>,
+        ph: &Vec<Option<PH>>,
+        rect: &Rect<f32>,
+        mask: u32,
+    ) -> Vec<usize>
+    where
+        P: Position,
+        PH: Physics,
+    {
+        let mut candidates = Vec::new();
+        self.grid.query(rect, &mut candidates);
+
+        candidates.retain(|&j| {
+            if j >= p.len() || p[j].is_none() || ph[j].is_none() { return false; }
+
+            let phy = ph[j].as_ref().unwrap();
+            phy.category() & mask != 0 &&
+                phy.shape().after_position(p[j].as_ref().unwrap()).overlaps(&Shape::Aabb(*rect))
+        });
+
+        candidates
+    }
+
+    /// Every entity whose shape contains the point, as of the last tick
+    pub fn query_point<P, PH>(
+        &self,
+        p: &Vec<Option<P>>,
+        ph: &Vec<Option<PH>>,
+        x: f32,
+        y: f32,
+        mask: u32,
+    ) -> Vec<usize>
+    where
+        P: Position,
+        PH: Physics,
+    {
+        let mut candidates = Vec::new();
+        self.grid.query(&Rect::new(x, y, 0.0, 0.0), &mut candidates);
+
+        candidates.retain(|&j| {
+            if j >= p.len() || p[j].is_none() || ph[j].is_none() { return false; }
+
+            let phy = ph[j].as_ref().unwrap();
+            phy.category() & mask != 0 &&
+                phy.shape().after_position(p[j].as_ref().unwrap()).contains_point(x, y)
+        });
+
+        candidates
+    }
+}
+
+fn normalize(v: (f32, f32)) -> Option<(f32, f32)> {
+    let len = (v.0 * v.0 + v.1 * v.1).sqrt();
+    if len == 0.0 { None } else { Some((v.0 / len, v.1 / len)) }
+}
+
+pub(crate) fn rect_polygon(rect: &Rect<f32>) -> [(f32, f32); 4] {
+    [
+        (rect.x, rect.y),
+        (rect.x + rect.w, rect.y),
+        (rect.x + rect.w, rect.y + rect.h),
+        (rect.x, rect.y + rect.h),
+    ]
+}
+
+/// Solid part of a slope tile, the area under its floor
+fn slope_polygon(kind: TileKind, rect: &Rect<f32>) -> [(f32, f32); 4] {
+    let bottom = rect.y + rect.h;
+    let left = kind.floor(0.0).unwrap_or(0.0) * rect.h;
+    let right = kind.floor(1.0).unwrap_or(0.0) * rect.h;
+
+    [
+        (rect.x, bottom - left),
+        (rect.x + rect.w, bottom - right),
+        (rect.x + rect.w, bottom),
+        (rect.x, bottom),
+    ]
+}
+
+/// Distance and surface normal where a ray enters a convex polygon (Cyrus-Beck clipping).
+/// Rays starting inside the polygon never hit it.
+pub(crate) fn ray_polygon(
+    origin: (f32, f32),
+    dir: (f32, f32),
+    verts: &[(f32, f32)],
+    max: f32
+) -> Option<(f32, (f32, f32))> {
+    let n = verts.len() as f32;
+    let center = verts.iter().fold((0.0, 0.0), |c, v| (c.0 + v.0 / n, c.1 + v.1 / n));
+
+    let mut t_enter = 0.0;
+    let mut t_exit = max;
+    let mut normal = None;
+
+    for k in 0..verts.len() {
+        let a = verts[k];
+        let b = verts[(k + 1) % verts.len()];
+
+        // Outward facing normal of the edge
+        let mut edge_normal = match normalize((b.1 - a.1, a.0 - b.0)) {
+            Some(n) => n,
+            None => continue
+        };
+        if edge_normal.0 * (center.0 - a.0) + edge_normal.1 * (center.1 - a.1) > 0.0 {
+            edge_normal = (-edge_normal.0, -edge_normal.1);
+        }
+
+        let dist = edge_normal.0 * (origin.0 - a.0) + edge_normal.1 * (origin.1 - a.1);
+        let speed = edge_normal.0 * dir.0 + edge_normal.1 * dir.1;
+
+        if speed == 0.0 {
+            // Parallel to the edge and outside of it
+            if dist > 0.0 { return None; }
+        } else if speed < 0.0 {
+            let t = -dist / speed;
+            if t >= t_enter {
+                t_enter = t;
+                normal = Some(edge_normal);
+            }
+        } else {
+            t_exit = t_exit.min(-dist / speed);
+        }
+
+        if t_enter > t_exit { return None; }
+    }
+
+    normal.map(|normal| (t_enter, normal))
+}
+
+/// Distance and surface normal where a ray enters a shape, rays starting inside never hit it
+pub(crate) fn ray_shape(
+    origin: (f32, f32),
+    dir: (f32, f32),
+    shape: &Shape,
+    max: f32
+) -> Option<(f32, (f32, f32))> {
+    match *shape {
+        Shape::Aabb(rect) => ray_polygon(origin, dir, &rect_polygon(&rect), max),
+        Shape::Circle { x, y, r } => ray_circle(origin, dir, (x, y), r, max),
+        Shape::Capsule { a, b, r } => {
+            if shape.contains_point(origin.0, origin.1) { return None; }
+
+            // Both end caps and the box between them, offset sideways by the radius
+            let side = match normalize((a.1 - b.1, b.0 - a.0)) {
+                Some(n) => (n.0 * r, n.1 * r),
+                None => return ray_circle(origin, dir, a, r, max)
+            };
+            let body = [
+                (a.0 + side.0, a.1 + side.1),
+                (b.0 + side.0, b.1 + side.1),
+                (b.0 - side.0, b.1 - side.1),
+                (a.0 - side.0, a.1 - side.1),
+            ];
+
+            [
+                ray_circle(origin, dir, a, r, max),
+                ray_circle(origin, dir, b, r, max),
+                ray_polygon(origin, dir, &body, max),
+            ]
+                .iter()
+                .flatten()
+                .fold(None, |best: Option<(f32, (f32, f32))>, &hit| match best {
+                    Some(b) if b.0 <= hit.0 => Some(b),
+                    _ => Some(hit),
+                })
+                .map(|(t, _)| {
+                    // The polygon normal is off at the corners, measure from the segment
+                    let point = (origin.0 + dir.0 * t, origin.1 + dir.1 * t);
+                    let core = closest_on_segment(a, b, point);
+                    (t, normalize((point.0 - core.0, point.1 - core.1)).unwrap_or((-dir.0, -dir.1)))
+                })
+        }
+    }
+}
+
+fn ray_circle(
+    origin: (f32, f32),
+    dir: (f32, f32),
+    center: (f32, f32),
+    r: f32,
+    max: f32
+) -> Option<(f32, (f32, f32))> {
+    let o = (origin.0 - center.0, origin.1 - center.1);
+    let b = o.0 * dir.0 + o.1 * dir.1;
+    let c = o.0 * o.0 + o.1 * o.1 - r * r;
+    let disc = b * b - c;
+    if disc < 0.0 { return None; }
+
+    let t = -b - disc.sqrt();
+    if t < 0.0 || t > max { return None; }
+
+    let point = (o.0 + dir.0 * t, o.1 + dir.1 * t);
+    Some((t, (point.0 / r, point.1 / r)))
+}
+
+fn ray_map<CM: CollisionMap>(
+    map: &CM,
+    origin: (f32, f32),
+    dir: (f32, f32),
+    max: f32,
+) -> Option<RayHit> {
+    let max = reach(map, origin, dir, max, (0.0, 0.0));
+    if max < 0.0 { return None; }
+
+    let size = match map.tile_size() {
+        Some(size) => size,
+        None => return march(origin, dir, max, |x, y| map.test(&Rect::new(x, y, EPSILON, EPSILON)))
+            .map(|(t, normal)| RayHit {
+                entity: None,
+                point: (origin.0 + dir.0 * t, origin.1 + dir.1 * t),
+                normal,
+                distance: t,
+            })
+    };
+
+    // Walk the tiles the ray passes through in order, the first hit is the closest
+    let mut tx = (origin.0 / size).floor() as i32;
+    let mut ty = (origin.1 / size).floor() as i32;
+    let step_x = if dir.0 > 0.0 { 1 } else { -1 };
+    let step_y = if dir.1 > 0.0 { 1 } else { -1 };
+    let delta_x = if dir.0 != 0.0 { size / dir.0.abs() } else { f32::INFINITY };
+    let delta_y = if dir.1 != 0.0 { size / dir.1.abs() } else { f32::INFINITY };
+    let mut next_x = match dir.0 {
+        d if d > 0.0 => ((tx + 1) as f32 * size - origin.0) / d,
+        d if d < 0.0 => (tx as f32 * size - origin.0) / d,
+        _ => f32::INFINITY
+    };
+    let mut next_y = match dir.1 {
+        d if d > 0.0 => ((ty + 1) as f32 * size - origin.1) / d,
+        d if d < 0.0 => (ty as f32 * size - origin.1) / d,
+        _ => f32::INFINITY
+    };
+
+    loop {
+        let trect = Rect::new(tx as f32 * size, ty as f32 * size, size, size);
+        let hit = match map.tile(tx, ty) {
+            TileKind::Empty => None,
+            TileKind::Solid => ray_polygon(origin, dir, &rect_polygon(&trect), max),
+            TileKind::OneWayUp => {
+                // Only the top can be hit, and only from above
+                if dir.1 > 0.0 && origin.1 <= trect.y {
+                    let t = (trect.y - origin.1) / dir.1;
+                    let x = origin.0 + dir.0 * t;
+                    if t <= max && x >= trect.x && x <= trect.x + trect.w {
+                        Some((t, (0.0, -1.0)))
+                    } else {
+                        None
+                    }
+                } else {
+                    None
+                }
+            }
+            kind => ray_polygon(origin, dir, &slope_polygon(kind, &trect), max),
+        };
+
+        if let Some((t, normal)) = hit {
+            return Some(RayHit {
+                entity: None,
+                point: (origin.0 + dir.0 * t, origin.1 + dir.1 * t),
+                normal,
+                distance: t,
+            });
+        }
+
+        if next_x < next_y {
+            if next_x > max { return None; }
+            tx += step_x;
+            next_x += delta_x;
+        } else {
+            if next_y > max { return None; }
+            ty += step_y;
+            next_y += delta_y;
+        }
+    }
+}
+
+fn box_map<CM: CollisionMap>(
+    map: &CM,
+    rect: &Rect<f32>,
+    dir: (f32, f32),
+    max: f32,
+) -> Option<RayHit> {
+    let origin = (rect.x, rect.y);
+    let max = reach(map, origin, dir, max, (rect.w, rect.h));
+    if max < 0.0 { return None; }
+
+    let mut bounds = *rect;
+    bounds.x += (dir.0 * max).min(0.0);
+    bounds.y += (dir.1 * max).min(0.0);
+    bounds.w += (dir.0 * max).abs();
+    bounds.h += (dir.1 * max).abs();
+
+    let size = match map.tile_size() {
+        Some(size) => size,
+        None => return march(origin, dir, max, |x, y| map.test(&Rect::new(x, y, rect.w, rect.h)))
+            .map(|(t, normal)| RayHit {
+                entity: None,
+                point: (origin.0 + dir.0 * t, origin.1 + dir.1 * t),
+                normal,
+                distance: t,
+            })
+    };
+
+    let foot = (rect.x + rect.w / 2.0, rect.y + rect.h);
+    let mut best: Option<(f32, (f32, f32))> = None;
+
+    let x0 = (bounds.x / size).floor() as i32;
+    let y0 = (bounds.y / size).floor() as i32;
+    let x1 = ((bounds.x + bounds.w) / size).floor() as i32;
+    let y1 = ((bounds.y + bounds.h) / size).floor() as i32;
+
+    for ty in y0..=y1 {
+        for tx in x0..=x1 {
+            let trect = Rect::new(tx as f32 * size, ty as f32 * size, size, size);
+            let limit = best.map_or(max, |b| b.0);
+
+            let hit = match map.tile(tx, ty) {
+                TileKind::Empty => None,
+                TileKind::Solid => {
+                    let grown = Rect::new(trect.x - rect.w, trect.y - rect.h, trect.w + rect.w, trect.h + rect.h);
+                    ray_polygon(origin, dir, &rect_polygon(&grown), limit)
+                }
+                TileKind::OneWayUp => {
+                    if dir.1 > 0.0 && foot.1 <= trect.y {
+                        let t = (trect.y - foot.1) / dir.1;
+                        let x = rect.x + dir.0 * t;
+                        if t <= limit && x + rect.w > trect.x && x < trect.x + trect.w {
+                            Some((t, (0.0, -1.0)))
+                        } else {
+                            None
+                        }
+                    } else {
+                        None
+                    }
+                }
+                kind => ray_polygon(foot, dir, &slope_polygon(kind, &trect), limit),
+            };
+
+            if let Some(hit) = hit {
+                best = Some(hit);
+            }
+        }
+    }
+
+    best.map(|(t, normal)| RayHit {
+        entity: None,
+        point: (origin.0 + dir.0 * t, origin.1 + dir.1 * t),
+        normal,
+        distance: t,
+    })
+}
+
+/// `max` shortened to where a ray, or the origin of a rect of `size`, leaves the map.
+/// Negative when it starts outside and moves away.
+fn reach<CM: CollisionMap>(map: &CM, origin: (f32, f32), dir: (f32, f32), max: f32, size: (f32, f32)) -> f32 {
+    let bounds = match map.bounds() {
+        Some(bounds) => bounds,
+        None => return max.min(MAX_MAP_DISTANCE),
+    };
+
+    let exit = |o: f32, d: f32, low: f32, high: f32| match d {
+        d if d > 0.0 => (high - o) / d,
+        d if d < 0.0 => (low - o) / d,
+        _ => f32::INFINITY
+    };
+
+    max.min(exit(origin.0, dir.0, bounds.x - size.0, bounds.x + bounds.w))
+        .min(exit(origin.1, dir.1, bounds.y - size.1, bounds.y + bounds.h))
+}
+
+/// Step along a ray until `solid` reports a hit, then binary search for the surface. Used for
+/// maps that can only be tested, not inspected.
+fn march<F: Fn(f32, f32) -> bool>(
+    origin: (f32, f32),
+    dir: (f32, f32),
+    max: f32,
+    solid: F,
+) -> Option<(f32, (f32, f32))> {
+    let at = |t: f32| (origin.0 + dir.0 * t, origin.1 + dir.1 * t);
+    if solid(origin.0, origin.1) { return None; }
+
+    let mut free = 0.0;
+    let mut t = 0.0;
+    loop {
+        t = (t + MARCH_STEP).min(max);
+        let (x, y) = at(t);
+        if solid(x, y) { break; }
+        if t >= max { return None; }
+        free = t;
+    }
+
+    let mut blocked = t;
+    for _ in 0..16 {
+        let mid = (free + blocked) / 2.0;
+        let (x, y) = at(mid);
+        if solid(x, y) { blocked = mid } else { free = mid }
+    }
+
+    // Back off along a single axis to find out which side was hit
+    let (x, y) = at(blocked);
+    let normal = if dir.0 != 0.0 && !solid(x - dir.0.signum() * MARCH_STEP, y) {
+        (-dir.0.signum(), 0.0)
+    } else {
+        (0.0, -dir.1.signum())
+    };
+
+    Some((free, normal))
+}
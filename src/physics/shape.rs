@@ -0,0 +1,399 @@
+use crate::rect::Rect;
+use crate::component::PositionComponent;
+use crate::physics::event::overlap_normal;
+
+/// Shapes closer than this are touching, not overlapping
+const TOLERANCE: f32 = 0.0001;
+
+/// Number of bisection steps used to find the time of impact in sweeps
+const SWEEP_ITERATIONS: usize = 16;
+
+/// Collision shape of a hitbox, relative to the entity position like `PhysicsComponent::hitbox`
+#[derive(Debug, Copy, Clone)]
+pub enum Shape {
+    Aabb(Rect<f32>),
+    Circle { x: f32, y: f32, r: f32 },
+    /// Segment from `a` to `b` grown by the radius `r`
+    Capsule { a: (f32, f32), b: (f32, f32), r: f32 },
+}
+
+impl Shape {
+    /// Upright capsule filling a rect, the usual shape for characters
+    pub fn capsule_in(rect: &Rect<f32>) -> Shape {
+        let r = rect.w.min(rect.h) / 2.0;
+        let cx = rect.x + rect.w / 2.0;
+
+        if rect.h >= rect.w {
+            Shape::Capsule { a: (cx, rect.y + r), b: (cx, rect.y + rect.h - r), r }
+        } else {
+            let cy = rect.y + rect.h / 2.0;
+            Shape::Capsule { a: (rect.x + r, cy), b: (rect.x + rect.w - r, cy), r }
+        }
+    }
+
+    pub fn translate(&self, dx: f32, dy: f32) -> Shape {
+        match *self {
+            Shape::Aabb(r) => Shape::Aabb(Rect::new(r.x + dx, r.y + dy, r.w, r.h)),
+            Shape::Circle { x, y, r } => Shape::Circle { x: x + dx, y: y + dy, r },
+            Shape::Capsule { a, b, r } => Shape::Capsule {
+                a: (a.0 + dx, a.1 + dy),
+                b: (b.0 + dx, b.1 + dy),
+                r
+            },
+        }
+    }
+
+    /// Same shape with every side pushed out by `margin`
+    pub fn grow(&self, margin: f32) -> Shape {
+        match *self {
+            Shape::Aabb(r) => Shape::Aabb(Rect::new(r.x - margin, r.y - margin, r.w + margin * 2.0, r.h + margin * 2.0)),
+            Shape::Circle { x, y, r } => Shape::Circle { x, y, r: r + margin },
+            Shape::Capsule { a, b, r } => Shape::Capsule { a, b, r: r + margin },
+        }
+    }
+
+    /// Create a new shape that has the offset of a position component
+    pub fn after_position<P: PositionComponent>(&self, pos: &P) -> Shape {
+        self.translate(pos.x(), pos.y())
+    }
+
+    /// Smallest rect containing the shape
+    pub fn bounds(&self) -> Rect<f32> {
+        match *self {
+            Shape::Aabb(r) => r,
+            Shape::Circle { x, y, r } => Rect::new(x - r, y - r, r * 2.0, r * 2.0),
+            Shape::Capsule { a, b, r } => Rect::new(
+                a.0.min(b.0) - r,
+                a.1.min(b.1) - r,
+                (a.0 - b.0).abs() + r * 2.0,
+                (a.1 - b.1).abs() + r * 2.0
+            ),
+        }
+    }
+
+    pub fn contains_point(&self, x: f32, y: f32) -> bool {
+        match *self {
+            Shape::Aabb(r) => r.contains_point(x, y),
+            Shape::Circle { x: cx, y: cy, r } => dist((x, y), (cx, cy)) <= r,
+            Shape::Capsule { a, b, r } => dist((x, y), closest_on_segment(a, b, (x, y))) <= r,
+        }
+    }
+
+    /// Check if this shape intersects in any way with another shape
+    pub fn overlaps(&self, other: &Shape) -> bool {
+        match (*self, *other) {
+            (Shape::Aabb(a), Shape::Aabb(b)) => a.has_intersection(&b),
+            (Shape::Aabb(rect), Shape::Circle { x, y, r }) |
+            (Shape::Circle { x, y, r }, Shape::Aabb(rect)) => {
+                dist((x, y), closest_in_rect(&rect, (x, y))) < r - TOLERANCE
+            }
+            (Shape::Aabb(rect), Shape::Capsule { a, b, r }) |
+            (Shape::Capsule { a, b, r }, Shape::Aabb(rect)) => {
+                segment_rect_dist(a, b, &rect) < r - TOLERANCE
+            }
+            (Shape::Circle { x: x1, y: y1, r: r1 }, Shape::Circle { x: x2, y: y2, r: r2 }) => {
+                dist((x1, y1), (x2, y2)) < r1 + r2 - TOLERANCE
+            }
+            (Shape::Circle { x, y, r: r1 }, Shape::Capsule { a, b, r: r2 }) |
+            (Shape::Capsule { a, b, r: r2 }, Shape::Circle { x, y, r: r1 }) => {
+                dist((x, y), closest_on_segment(a, b, (x, y))) < r1 + r2 - TOLERANCE
+            }
+            (Shape::Capsule { a: a1, b: b1, r: r1 }, Shape::Capsule { a: a2, b: b2, r: r2 }) => {
+                segment_dist(a1, b1, a2, b2) < r1 + r2 - TOLERANCE
+            }
+        }
+    }
+
+    /// Fraction of the movement `(dx, dy)` this shape can travel before it touches `other`, or
+    /// `None` if it never does. Shapes that already overlap never hit each other.
+    pub fn sweep(&self, other: &Shape, dx: f32, dy: f32) -> Option<f32> {
+        if self.overlaps(other) { return None; }
+
+        // Step in increments smaller than either shape so nothing is tunneled through, then
+        // binary search between the last free step and the first blocked one
+        let (a, b) = (self.bounds(), other.bounds());
+        let stride = (a.w.min(a.h).min(b.w).min(b.h) / 2.0).max(TOLERANCE);
+        let steps = ((dx * dx + dy * dy).sqrt() / stride).ceil().max(1.0) as usize;
+
+        let mut free = 0.0;
+        let mut blocked = None;
+        for step in 1..=steps {
+            let f = step as f32 / steps as f32;
+            if self.translate(dx * f, dy * f).overlaps(other) {
+                blocked = Some(f);
+                break;
+            }
+            free = f;
+        }
+
+        let mut blocked = blocked?;
+        for _ in 0..SWEEP_ITERATIONS {
+            let mid = (free + blocked) / 2.0;
+            if self.translate(dx * mid, dy * mid).overlaps(other) {
+                blocked = mid;
+            } else {
+                free = mid;
+            }
+        }
+
+        Some(free)
+    }
+
+    /// Normal of the surface of `other` closest to this shape, pointing towards this shape
+    pub fn normal(&self, other: &Shape) -> (f32, f32) {
+        // Measure from the middle of a round part, which stays well defined while touching
+        let normal = match (self.core(other), other.core(self)) {
+            (Some(c), _) => {
+                let s = other.closest_point(c);
+                normalize((c.0 - s.0, c.1 - s.1))
+            }
+            (None, Some(c)) => {
+                let s = self.closest_point(c);
+                normalize((s.0 - c.0, s.1 - c.1))
+            }
+            (None, None) => None,
+        };
+
+        normal.unwrap_or_else(|| {
+            // Boxes, or the middles overlap, fall back to the bounds
+            let (a, b) = (self.bounds(), other.bounds());
+            let dx = (a.x + a.w / 2.0) - (b.x + b.w / 2.0);
+            let dy = (a.y + a.h / 2.0) - (b.y + b.h / 2.0);
+            if dx.abs() > dy.abs() { (dx.signum(), 0.0) } else { (0.0, dy.signum()) }
+        })
+    }
+
+    /// Minimum translation that separates this shape from `other`, as the direction to move
+    /// this shape in and the distance, or `None` when they do not overlap
+    pub fn penetration(&self, other: &Shape) -> Option<((f32, f32), f32)> {
+        if !self.overlaps(other) { return None; }
+
+        // Every shape is a box, point or segment grown by a radius, so the depth is the sum of
+        // the radii minus the distance between those inner parts
+        let round = |shape: &Shape, other: &Shape| {
+            let c = shape.core(other)?;
+            let s = other.inner_closest(c);
+            let d = dist(c, s);
+            if d < TOLERANCE { return None; }
+
+            Some((((c.0 - s.0) / d, (c.1 - s.1) / d), shape.radius() + other.radius() - d))
+        };
+
+        let mtv = round(self, other)
+            .or_else(|| round(other, self).map(|(n, depth)| ((-n.0, -n.1), depth)));
+
+        Some(mtv.unwrap_or_else(|| {
+            // Boxes, or an inner part is inside the other shape, separate the bounds instead
+            let (a, b) = (self.bounds(), other.bounds());
+            let normal = overlap_normal(&a, &b);
+            let depth = if normal.0 != 0.0 {
+                (a.w + b.w) / 2.0 - ((a.x + a.w / 2.0) - (b.x + b.w / 2.0)).abs()
+            } else {
+                (a.h + b.h) / 2.0 - ((a.y + a.h / 2.0) - (b.y + b.h / 2.0)).abs()
+            };
+            (normal, depth)
+        }))
+    }
+
+    fn radius(&self) -> f32 {
+        match *self {
+            Shape::Aabb(_) => 0.0,
+            Shape::Circle { r, .. } | Shape::Capsule { r, .. } => r,
+        }
+    }
+
+    /// Point of the shape without its radius closest to `p`
+    fn inner_closest(&self, p: (f32, f32)) -> (f32, f32) {
+        match *self {
+            Shape::Aabb(rect) => closest_in_rect(&rect, p),
+            Shape::Circle { x, y, .. } => (x, y),
+            Shape::Capsule { a, b, .. } => closest_on_segment(a, b, p),
+        }
+    }
+
+    /// Center of the round part of the shape facing `other`, boxes have none
+    fn core(&self, other: &Shape) -> Option<(f32, f32)> {
+        match *self {
+            Shape::Aabb(_) => None,
+            Shape::Circle { x, y, .. } => Some((x, y)),
+            Shape::Capsule { a, b, .. } => Some(closest_on_segment(a, b, other.center())),
+        }
+    }
+
+    fn center(&self) -> (f32, f32) {
+        let b = self.bounds();
+        (b.x + b.w / 2.0, b.y + b.h / 2.0)
+    }
+
+    /// Point of the shape closest to `p`, `p` itself when it is inside
+    fn closest_point(&self, p: (f32, f32)) -> (f32, f32) {
+        match *self {
+            Shape::Aabb(rect) => closest_in_rect(&rect, p),
+            Shape::Circle { x, y, r } => toward((x, y), p, r),
+            Shape::Capsule { a, b, r } => toward(closest_on_segment(a, b, p), p, r),
+        }
+    }
+}
+
+fn dist(a: (f32, f32), b: (f32, f32)) -> f32 {
+    ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt()
+}
+
+fn normalize(v: (f32, f32)) -> Option<(f32, f32)> {
+    let len = (v.0 * v.0 + v.1 * v.1).sqrt();
+    if len < TOLERANCE { None } else { Some((v.0 / len, v.1 / len)) }
+}
+
+/// Move from `center` towards `p`, but no further than `r`
+fn toward(center: (f32, f32), p: (f32, f32), r: f32) -> (f32, f32) {
+    let d = dist(center, p);
+    if d <= r { return p; }
+    (center.0 + (p.0 - center.0) / d * r, center.1 + (p.1 - center.1) / d * r)
+}
+
+fn closest_in_rect(rect: &Rect<f32>, p: (f32, f32)) -> (f32, f32) {
+    (p.0.clamp(rect.x, rect.x + rect.w), p.1.clamp(rect.y, rect.y + rect.h))
+}
+
+pub(crate) fn closest_on_segment(a: (f32, f32), b: (f32, f32), p: (f32, f32)) -> (f32, f32) {
+    let ab = (b.0 - a.0, b.1 - a.1);
+    let len = ab.0 * ab.0 + ab.1 * ab.1;
+    if len == 0.0 { return a; }
+
+    let t = (((p.0 - a.0) * ab.0 + (p.1 - a.1) * ab.1) / len).clamp(0.0, 1.0);
+    (a.0 + ab.0 * t, a.1 + ab.1 * t)
+}
+
+fn cross(o: (f32, f32), a: (f32, f32), b: (f32, f32)) -> f32 {
+    (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0)
+}
+
+fn segments_intersect(a1: (f32, f32), b1: (f32, f32), a2: (f32, f32), b2: (f32, f32)) -> bool {
+    let d1 = cross(a2, b2, a1);
+    let d2 = cross(a2, b2, b1);
+    let d3 = cross(a1, b1, a2);
+    let d4 = cross(a1, b1, b2);
+
+    d1 * d2 < 0.0 && d3 * d4 < 0.0
+}
+
+/// Shortest distance between two segments
+fn segment_dist(a1: (f32, f32), b1: (f32, f32), a2: (f32, f32), b2: (f32, f32)) -> f32 {
+    if segments_intersect(a1, b1, a2, b2) { return 0.0; }
+
+    dist(a1, closest_on_segment(a2, b2, a1))
+        .min(dist(b1, closest_on_segment(a2, b2, b1)))
+        .min(dist(a2, closest_on_segment(a1, b1, a2)))
+        .min(dist(b2, closest_on_segment(a1, b1, b2)))
+}
+
+/// Shortest distance between a segment and a filled rect
+fn segment_rect_dist(a: (f32, f32), b: (f32, f32), rect: &Rect<f32>) -> f32 {
+    if rect.contains_point(a.0, a.1) || rect.contains_point(b.0, b.1) { return 0.0; }
+
+    let corners = [
+        (rect.x, rect.y),
+        (rect.x + rect.w, rect.y),
+        (rect.x + rect.w, rect.y + rect.h),
+        (rect.x, rect.y + rect.h),
+    ];
+
+    (0..4)
+        .map(|k| segment_dist(a, b, corners[k], corners[(k + 1) % 4]))
+        .fold(f32::INFINITY, f32::min)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn aabb(x: f32, y: f32) -> Shape {
+        Shape::Aabb(Rect::new(x, y, 10.0, 10.0))
+    }
+
+    fn circle(x: f32, y: f32) -> Shape {
+        Shape::Circle { x, y, r: 5.0 }
+    }
+
+    /// Upright capsule 10 wide and 20 high, with its top left corner at `(x, y)`
+    fn capsule(x: f32, y: f32) -> Shape {
+        Shape::capsule_in(&Rect::new(x, y, 10.0, 20.0))
+    }
+
+    /// `make(x)` gives the second shape with its left side at `x`, the first shape's right
+    /// side is at 10
+    fn check(first: Shape, make: fn(f32, f32) -> Shape) {
+        let touching = make(10.0, 0.0);
+        let overlapping = make(8.0, 0.0);
+        let separated = make(12.0, 0.0);
+
+        for (a, b) in [(first, touching), (touching, first)] {
+            assert!(!a.overlaps(&b), "{:?} and {:?} only touch", a, b);
+            assert!(a.penetration(&b).is_none());
+        }
+
+        for (a, b) in [(first, overlapping), (overlapping, first)] {
+            assert!(a.overlaps(&b), "{:?} and {:?} overlap", a, b);
+            let (_, depth) = a.penetration(&b).unwrap();
+            assert!((depth - 2.0).abs() < 0.01, "{:?} and {:?} overlap by {}", a, b, depth);
+        }
+
+        for (a, b) in [(first, separated), (separated, first)] {
+            assert!(!a.overlaps(&b), "{:?} and {:?} are apart", a, b);
+            assert!(a.penetration(&b).is_none());
+        }
+
+        let f = first.sweep(&separated, 4.0, 0.0).unwrap();
+        assert!((f - 0.5).abs() < 0.01, "{:?} reaches {:?} halfway, not at {}", first, separated, f);
+        assert!(first.sweep(&separated, 1.0, 0.0).is_none());
+
+        let normal = first.normal(&touching);
+        assert!((normal.0 + 1.0).abs() < 0.01 && normal.1.abs() < 0.01, "{:?}", normal);
+    }
+
+    // Round shapes are placed so their widest part lines up with the others
+
+    #[test]
+    fn aabb_aabb() {
+        check(aabb(0.0, 0.0), aabb);
+    }
+
+    #[test]
+    fn aabb_circle() {
+        check(aabb(0.0, 0.0), |x, y| circle(x + 5.0, y + 5.0));
+        check(circle(5.0, 5.0), aabb);
+    }
+
+    #[test]
+    fn aabb_capsule() {
+        check(aabb(0.0, 5.0), capsule);
+        check(capsule(0.0, 0.0), |x, y| aabb(x, y + 5.0));
+    }
+
+    #[test]
+    fn circle_circle() {
+        check(circle(5.0, 5.0), |x, y| circle(x + 5.0, y + 5.0));
+    }
+
+    #[test]
+    fn circle_capsule() {
+        check(circle(5.0, 10.0), capsule);
+        check(capsule(0.0, 0.0), |x, y| circle(x + 5.0, y + 10.0));
+    }
+
+    #[test]
+    fn capsule_capsule() {
+        check(capsule(0.0, 0.0), capsule);
+    }
+
+    #[test]
+    fn sweep_steps_by_the_smaller_shape() {
+        // Grazed between two steps of the size of the ball
+        let pebble = Shape::Circle { x: 110.0, y: 20.5, r: 1.0 };
+        let ball = Shape::Circle { x: 0.0, y: 0.0, r: 20.0 };
+
+        let f = ball.sweep(&pebble, 200.0, 0.0).unwrap();
+        let expected = (110.0 - (21.0f32 * 21.0 - 20.5 * 20.5).sqrt()) / 200.0;
+        assert!((f - expected).abs() < 0.001);
+    }
+}
@@ -0,0 +1,158 @@
+use std::cell::{Ref, RefMut};
+use std::slice::{Iter, IterMut};
+
+/// Component `Vec` walked in step with others by a `Query`
+pub trait Column {
+    type Item;
+
+    /// The next slot, `None` once the column ran out and `Some(None)` for an entity without
+    /// the component
+    fn next_slot(&mut self) -> Option<Option<Self::Item>>;
+}
+
+/// Anything a `Query` can be made from: `&Vec<Option<T>>` reads components, `&mut Vec<Option<T>>`
+/// writes them, `Maybe` makes a component optional, and tuples combine them
+pub trait IntoColumn {
+    type Column: Column;
+
+    fn into_column(self) -> Self::Column;
+}
+
+pub struct Read<'a, T>(Iter<'a, Option<T>>);
+
+impl<'a, T> Column for Read<'a, T> {
+    type Item = &'a T;
+
+    fn next_slot(&mut self) -> Option<Option<&'a T>> {
+        self.0.next().map(|slot| slot.as_ref())
+    }
+}
+
+pub struct Write<'a, T>(IterMut<'a, Option<T>>);
+
+impl<'a, T> Column for Write<'a, T> {
+    type Item = &'a mut T;
+
+    fn next_slot(&mut self) -> Option<Option<&'a mut T>> {
+        self.0.next().map(|slot| slot.as_mut())
+    }
+}
+
+/// Optional component, entities without it are still visited. Never ends the query, so a query
+/// needs at least one component that is not optional.
+pub struct Maybe<C>(pub C);
+
+impl<C: Column> Column for Maybe<C> {
+    type Item = Option<C::Item>;
+
+    fn next_slot(&mut self) -> Option<Option<Option<C::Item>>> {
+        Some(Some(self.0.next_slot().flatten()))
+    }
+}
+
+impl<C: IntoColumn> IntoColumn for Maybe<C> {
+    type Column = Maybe<C::Column>;
+
+    fn into_column(self) -> Maybe<C::Column> {
+        Maybe(self.0.into_column())
+    }
+}
+
+impl<'a, T> IntoColumn for &'a Vec<Option<T>> {
+    type Column = Read<'a, T>;
+
+    fn into_column(self) -> Read<'a, T> {
+        Read(self.iter())
+    }
+}
+
+impl<'a, T> IntoColumn for &'a mut Vec<Option<T>> {
+    type Column = Write<'a, T>;
+
+    fn into_column(self) -> Write<'a, T> {
+        Write(self.iter_mut())
+    }
+}
+
+// Storages borrowed from a `World`
+impl<'a, 'b, T> IntoColumn for &'a Ref<'b, Vec<Option<T>>> {
+    type Column = Read<'a, T>;
+
+    fn into_column(self) -> Read<'a, T> {
+        Read(self.iter())
+    }
+}
+
+impl<'a, 'b, T> IntoColumn for &'a mut RefMut<'b, Vec<Option<T>>> {
+    type Column = Write<'a, T>;
+
+    fn into_column(self) -> Write<'a, T> {
+        Write(self.iter_mut())
+    }
+}
+
+macro_rules! tuple_column {
+    ($($name:ident),+) => {
+        #[allow(non_snake_case)]
+        impl<$($name: Column),+> Column for ($($name,)+) {
+            type Item = ($($name::Item,)+);
+
+            fn next_slot(&mut self) -> Option<Option<Self::Item>> {
+                let ($($name,)+) = self;
+
+                // Every column has to move on, even when an earlier one is missing
+                $(let $name = $name.next_slot()?;)+
+                Some((|| Some(($($name?,)+)))())
+            }
+        }
+
+        impl<$($name: IntoColumn),+> IntoColumn for ($($name,)+) {
+            type Column = ($($name::Column,)+);
+
+            #[allow(non_snake_case)]
+            fn into_column(self) -> Self::Column {
+                let ($($name,)+) = self;
+                ($($name.into_column(),)+)
+            }
+        }
+    };
+}
+
+tuple_column!(A);
+tuple_column!(A, B);
+tuple_column!(A, B, C);
+tuple_column!(A, B, C, D);
+tuple_column!(A, B, C, D, E);
+tuple_column!(A, B, C, D, E, F);
+
+/// Iterator over the entities having every component of a query, yielding the index of each
+/// entity along with its components
+pub struct Query<C> {
+    columns: C,
+    index: usize,
+}
+
+impl<C: Column> Iterator for Query<C> {
+    type Item = (usize, C::Item);
+
+    fn next(&mut self) -> Option<(usize, C::Item)> {
+        loop {
+            let slot = self.columns.next_slot()?;
+            let index = self.index;
+            self.index += 1;
+
+            if let Some(item) = slot {
+                return Some((index, item));
+            }
+        }
+    }
+}
+
+/// Iterate the entities having a set of components, e.g.
+/// `query((&mut p, &v, Maybe(&b)))` yields `(i, (&mut P, &V, Option<&B>))`
+pub fn query<Q: IntoColumn>(columns: Q) -> Query<Q::Column> {
+    Query {
+        columns: columns.into_column(),
+        index: 0,
+    }
+}
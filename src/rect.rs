@@ -8,6 +8,9 @@ use serde::{Serialize, Deserialize};
 
 const TOLERANCE: f32 = 0.0001;
 
+/// Corners of a quad, clockwise from the top left
+pub type Quad = [(f32, f32); 4];
+
 pub trait IntoF32 {
     fn to_f32(self) -> f32;
 }
@@ -56,44 +59,24 @@ where T: IntoF32
 impl Rect<f32> {
     /// Create vertices for upload to gpu
     pub fn vertices(&self, tex: &Texture, atlas: &Atlas) -> [Vertex; 4] {
-        let nw = tex.nw();
-        let ne = tex.ne();
-        let sw = tex.sw();
-        let se = tex.se();
+        self.tinted_vertices(tex, atlas, [1.0; 4])
+    }
+
+    /// Vertices with the texture color multiplied by a tint
+    pub fn tinted_vertices(&self, tex: &Texture, atlas: &Atlas, tint: [f32; 4]) -> [Vertex; 4] {
+        quad_vertices(self.corners(), tex, atlas, tint)
+    }
 
+    pub fn corners(&self) -> Quad {
         [
-            Vertex {
-                position: [self.x, self.y],
-                tex_coords: [
-                    nw[0] as f32 / atlas.width as f32,
-                    nw[1] as f32 / atlas.height as f32
-                ]
-            },
-            Vertex {
-                position: [self.x + self.w, self.y],
-                tex_coords: [
-                    ne[0] as f32 / atlas.width as f32,
-                    ne[1] as f32 / atlas.height as f32
-                ]
-            },
-            Vertex {
-                position: [self.x + self.w, self.y + self.h],
-                tex_coords: [
-                    se[0] as f32 / atlas.width as f32,
-                    se[1] as f32 / atlas.height as f32
-                ]
-            },
-            Vertex {
-                position: [self.x, self.y + self.h],
-                tex_coords: [
-                    sw[0] as f32 / atlas.width as f32,
-                    sw[1] as f32 / atlas.height as f32
-                ]
-            }
+            (self.x, self.y),
+            (self.x + self.w, self.y),
+            (self.x + self.w, self.y + self.h),
+            (self.x, self.y + self.h),
         ]
     }
 
-    pub fn indices(&self, vert_index: u16) -> [u16; 6] {
+    pub fn indices(&self, vert_index: u32) -> [u32; 6] {
         [vert_index, vert_index+1, vert_index+2, vert_index+2, vert_index+3, vert_index]
     }
 
@@ -131,3 +114,59 @@ where
         true
     }
 }
+
+/// Vertices of a quad with any corners, e.g. a rotated sprite
+pub fn quad_vertices(corners: Quad, tex: &Texture, atlas: &Atlas, tint: [f32; 4]) -> [Vertex; 4] {
+    // Trimmed textures only cover part of the quad
+    let trim = tex.trim();
+    let at = |u: f32, v: f32| (
+        corners[0].0 + (corners[1].0 - corners[0].0) * u + (corners[3].0 - corners[0].0) * v,
+        corners[0].1 + (corners[1].1 - corners[0].1) * u + (corners[3].1 - corners[0].1) * v,
+    );
+    let corners = [
+        at(trim.x, trim.y),
+        at(trim.x + trim.w, trim.y),
+        at(trim.x + trim.w, trim.y + trim.h),
+        at(trim.x, trim.y + trim.h),
+    ];
+
+    let nw = tex.nw();
+    let ne = tex.ne();
+    let sw = tex.sw();
+    let se = tex.se();
+
+    [
+        Vertex {
+            position: [corners[0].0, corners[0].1],
+            tex_coords: [
+                nw[0] as f32 / atlas.width as f32,
+                nw[1] as f32 / atlas.height as f32
+            ],
+            color: tint
+        },
+        Vertex {
+            position: [corners[1].0, corners[1].1],
+            tex_coords: [
+                ne[0] as f32 / atlas.width as f32,
+                ne[1] as f32 / atlas.height as f32
+            ],
+            color: tint
+        },
+        Vertex {
+            position: [corners[2].0, corners[2].1],
+            tex_coords: [
+                se[0] as f32 / atlas.width as f32,
+                se[1] as f32 / atlas.height as f32
+            ],
+            color: tint
+        },
+        Vertex {
+            position: [corners[3].0, corners[3].1],
+            tex_coords: [
+                sw[0] as f32 / atlas.width as f32,
+                sw[1] as f32 / atlas.height as f32
+            ],
+            color: tint
+        }
+    ]
+}
@@ -0,0 +1,590 @@
+use std::f32::consts::PI;
+
+use crate::component::{PositionComponent, SpriteComponent};
+use crate::graphics::Camera;
+use crate::query::query;
+use crate::rect::Rect;
+use crate::world::World;
+
+/// Curve mapping the progress of a tween, from 0 to 1, to how far along its value is
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Easing {
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    /// Overshoots back and forth like a spring
+    ElasticIn,
+    ElasticOut,
+    ElasticInOut,
+    /// Overshoots a little
+    BackIn,
+    BackOut,
+    BackInOut,
+    BounceIn,
+    BounceOut,
+    BounceInOut,
+}
+
+impl Easing {
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+
+        // Constants of the usual back and elastic curves
+        let back = 1.70158;
+        let back_in_out = back * 1.525;
+        let elastic = 2.0 * PI / 3.0;
+        let elastic_in_out = 2.0 * PI / 4.5;
+
+        match self {
+            Easing::Linear => t,
+            Easing::QuadIn => t * t,
+            Easing::QuadOut => 1.0 - (1.0 - t).powi(2),
+            Easing::QuadInOut => {
+                if t < 0.5 { 2.0 * t * t } else { 1.0 - (-2.0 * t + 2.0).powi(2) / 2.0 }
+            }
+            Easing::CubicIn => t * t * t,
+            Easing::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Easing::CubicInOut => {
+                if t < 0.5 { 4.0 * t * t * t } else { 1.0 - (-2.0 * t + 2.0).powi(3) / 2.0 }
+            }
+            Easing::ElasticIn => {
+                if t == 0.0 || t == 1.0 { return t; }
+                -(2.0f32).powf(10.0 * t - 10.0) * ((10.0 * t - 10.75) * elastic).sin()
+            }
+            Easing::ElasticOut => {
+                if t == 0.0 || t == 1.0 { return t; }
+                (2.0f32).powf(-10.0 * t) * ((10.0 * t - 0.75) * elastic).sin() + 1.0
+            }
+            Easing::ElasticInOut => {
+                if t == 0.0 || t == 1.0 { return t; }
+                let wave = ((20.0 * t - 11.125) * elastic_in_out).sin();
+
+                if t < 0.5 {
+                    -(2.0f32).powf(20.0 * t - 10.0) * wave / 2.0
+                } else {
+                    (2.0f32).powf(-20.0 * t + 10.0) * wave / 2.0 + 1.0
+                }
+            }
+            Easing::BackIn => (back + 1.0) * t * t * t - back * t * t,
+            Easing::BackOut => 1.0 + (back + 1.0) * (t - 1.0).powi(3) + back * (t - 1.0).powi(2),
+            Easing::BackInOut => {
+                if t < 0.5 {
+                    (2.0 * t).powi(2) * ((back_in_out + 1.0) * 2.0 * t - back_in_out) / 2.0
+                } else {
+                    ((2.0 * t - 2.0).powi(2) * ((back_in_out + 1.0) * (2.0 * t - 2.0) + back_in_out) + 2.0) / 2.0
+                }
+            }
+            Easing::BounceIn => 1.0 - bounce_out(1.0 - t),
+            Easing::BounceOut => bounce_out(t),
+            Easing::BounceInOut => {
+                if t < 0.5 {
+                    (1.0 - bounce_out(1.0 - 2.0 * t)) / 2.0
+                } else {
+                    (1.0 + bounce_out(2.0 * t - 1.0)) / 2.0
+                }
+            }
+        }
+    }
+}
+
+fn bounce_out(t: f32) -> f32 {
+    let n = 7.5625;
+    let d = 2.75;
+
+    if t < 1.0 / d {
+        n * t * t
+    } else if t < 2.0 / d {
+        let t = t - 1.5 / d;
+        n * t * t + 0.75
+    } else if t < 2.5 / d {
+        let t = t - 2.25 / d;
+        n * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / d;
+        n * t * t + 0.984375
+    }
+}
+
+/// Values a tween can move between
+pub trait Lerp: Copy {
+    fn lerp(self, to: Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(self, to: f32, t: f32) -> f32 {
+        self + (to - self) * t
+    }
+}
+
+/// Positions
+impl Lerp for (f32, f32) {
+    fn lerp(self, to: (f32, f32), t: f32) -> (f32, f32) {
+        (self.0.lerp(to.0, t), self.1.lerp(to.1, t))
+    }
+}
+
+/// Colors
+impl Lerp for [f32; 4] {
+    fn lerp(self, to: [f32; 4], t: f32) -> [f32; 4] {
+        [
+            self[0].lerp(to[0], t),
+            self[1].lerp(to[1], t),
+            self[2].lerp(to[2], t),
+            self[3].lerp(to[3], t),
+        ]
+    }
+}
+
+/// Rects
+impl Lerp for Rect<f32> {
+    fn lerp(self, to: Rect<f32>, t: f32) -> Rect<f32> {
+        Rect {
+            x: self.x.lerp(to.x, t),
+            y: self.y.lerp(to.y, t),
+            w: self.w.lerp(to.w, t),
+            h: self.h.lerp(to.h, t),
+        }
+    }
+}
+
+/// Something that changes a value over time: a `Tween`, a `Sequence` of them, or a tuple of
+/// them played at the same time
+pub trait Tweened {
+    type Value;
+
+    /// Move on by `dt` seconds, returning the part of `dt` left over after it finished
+    fn advance(&mut self, dt: f32) -> f32;
+
+    fn value(&self) -> Self::Value;
+
+    fn finished(&self) -> bool;
+
+    fn restart(&mut self);
+}
+
+/// Eases a value from one end to the other
+#[derive(Debug, Clone)]
+pub struct Tween<T: Lerp> {
+    from: T,
+    to: T,
+    duration: f32,
+    easing: Easing,
+    /// Seconds to wait before starting
+    delay: f32,
+    /// Extra passes after the first one, `None` repeats forever
+    repeat: Option<u32>,
+    /// Every other pass goes back from `to` to `from`
+    yoyo: bool,
+    waited: f32,
+    elapsed: f32,
+    pass: u32,
+    finished: bool,
+}
+
+impl<T: Lerp> Tween<T> {
+    pub fn new(from: T, to: T, duration: f32, easing: Easing) -> Tween<T> {
+        assert!(duration > 0.0, "Tween without duration");
+
+        Tween {
+            from,
+            to,
+            duration,
+            easing,
+            delay: 0.0,
+            repeat: Some(0),
+            yoyo: false,
+            waited: 0.0,
+            elapsed: 0.0,
+            pass: 0,
+            finished: false,
+        }
+    }
+
+    pub fn delay(mut self, delay: f32) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Play `times` more passes after the first one
+    pub fn repeat(mut self, times: u32) -> Self {
+        self.repeat = Some(times);
+        self
+    }
+
+    pub fn forever(mut self) -> Self {
+        self.repeat = None;
+        self
+    }
+
+    pub fn yoyo(mut self, yoyo: bool) -> Self {
+        self.yoyo = yoyo;
+        self
+    }
+
+    fn last_pass(&self) -> bool {
+        matches!(self.repeat, Some(repeat) if self.pass >= repeat)
+    }
+}
+
+impl<T: Lerp> Tweened for Tween<T> {
+    type Value = T;
+
+    fn advance(&mut self, dt: f32) -> f32 {
+        if self.finished { return dt; }
+
+        let wait = (self.delay - self.waited).clamp(0.0, dt);
+        self.waited += wait;
+        self.elapsed += dt - wait;
+
+        while self.elapsed >= self.duration {
+            if self.last_pass() {
+                let left = self.elapsed - self.duration;
+                self.elapsed = self.duration;
+                self.finished = true;
+                return left;
+            }
+
+            self.elapsed -= self.duration;
+            self.pass += 1;
+        }
+
+        0.0
+    }
+
+    fn value(&self) -> T {
+        let mut t = self.elapsed / self.duration;
+        if self.yoyo && self.pass % 2 == 1 {
+            t = 1.0 - t;
+        }
+
+        self.from.lerp(self.to, self.easing.apply(t))
+    }
+
+    fn finished(&self) -> bool {
+        self.finished
+    }
+
+    fn restart(&mut self) {
+        self.waited = 0.0;
+        self.elapsed = 0.0;
+        self.pass = 0;
+        self.finished = false;
+    }
+}
+
+/// Tweens played one after the other, e.g. a path through several points
+#[derive(Debug, Clone)]
+pub struct Sequence<A: Tweened> {
+    steps: Vec<A>,
+    current: usize,
+}
+
+impl<A: Tweened> Sequence<A> {
+    pub fn new(steps: Vec<A>) -> Sequence<A> {
+        assert!(!steps.is_empty(), "Sequence without steps");
+        Sequence { steps, current: 0 }
+    }
+
+    /// Index of the step being played
+    pub fn current(&self) -> usize {
+        self.current.min(self.steps.len() - 1)
+    }
+}
+
+impl<A: Tweened> Tweened for Sequence<A> {
+    type Value = A::Value;
+
+    fn advance(&mut self, mut dt: f32) -> f32 {
+        while self.current < self.steps.len() {
+            dt = self.steps[self.current].advance(dt);
+            if !self.steps[self.current].finished() { return 0.0; }
+
+            self.current += 1;
+        }
+
+        dt
+    }
+
+    fn value(&self) -> A::Value {
+        self.steps[self.current()].value()
+    }
+
+    fn finished(&self) -> bool {
+        self.current >= self.steps.len()
+    }
+
+    fn restart(&mut self) {
+        for step in self.steps.iter_mut() {
+            step.restart();
+        }
+        self.current = 0;
+    }
+}
+
+// Tuples play their tweens at the same time and finish with the last one
+macro_rules! tuple_tweened {
+    ($($name:ident),+) => {
+        #[allow(non_snake_case)]
+        impl<$($name: Tweened),+> Tweened for ($($name,)+) {
+            type Value = ($($name::Value,)+);
+
+            fn advance(&mut self, dt: f32) -> f32 {
+                let ($($name,)+) = self;
+                let mut left = dt;
+                $(left = left.min($name.advance(dt));)+
+                left
+            }
+
+            fn value(&self) -> Self::Value {
+                let ($($name,)+) = self;
+                ($($name.value(),)+)
+            }
+
+            fn finished(&self) -> bool {
+                let ($($name,)+) = self;
+                true $(&& $name.finished())+
+            }
+
+            fn restart(&mut self) {
+                let ($($name,)+) = self;
+                $($name.restart();)+
+            }
+        }
+    };
+}
+
+tuple_tweened!(A, B);
+tuple_tweened!(A, B, C);
+tuple_tweened!(A, B, C, D);
+
+/// Advance position tweens and move the entities to their values
+pub fn positions<P, T>(p: &mut Vec<Option<P>>, t: &mut Vec<Option<T>>, dt: f32)
+where
+    P: PositionComponent,
+    T: Tweened<Value = (f32, f32)>,
+{
+    for (_, (pos, tween)) in query((p, t)) {
+        if tween.finished() { continue; }
+
+        tween.advance(dt);
+        let (x, y) = tween.value();
+        pos.set_x(x);
+        pos.set_y(y);
+    }
+}
+
+/// Advance color tweens and tint the sprites with their values
+pub fn tints<G, T>(g: &mut Vec<Option<G>>, t: &mut Vec<Option<T>>, dt: f32)
+where
+    G: SpriteComponent,
+    T: Tweened<Value = [f32; 4]>,
+{
+    for (_, (sprite, tween)) in query((g, t)) {
+        if tween.finished() { continue; }
+
+        tween.advance(dt);
+        sprite.set_tint(tween.value());
+    }
+}
+
+/// Advance a tween of the camera position, e.g. to pan over to a point of interest
+pub fn camera<C, T>(camera: &mut C, tween: &mut T, dt: f32)
+where
+    C: Camera,
+    T: Tweened<Value = (f32, f32)>,
+{
+    if tween.finished() { return; }
+
+    tween.advance(dt);
+    let (x, y) = tween.value();
+    camera.set_position(x, y);
+}
+
+/// Advance a tween of the size of the camera view, e.g. to zoom in on a boss
+pub fn camera_size<C, T>(camera: &mut C, tween: &mut T, dt: f32)
+where
+    C: Camera,
+    T: Tweened<Value = (f32, f32)>,
+{
+    if tween.finished() { return; }
+
+    tween.advance(dt);
+    let (w, h) = tween.value();
+    camera.set_size(w, h);
+}
+
+/// `positions` over the components of a world
+pub fn positions_world<P, T>(world: &World, dt: f32)
+where
+    P: PositionComponent + 'static,
+    T: Tweened<Value = (f32, f32)> + 'static,
+{
+    positions(&mut world.borrow_mut::<P>(), &mut world.borrow_mut::<T>(), dt);
+}
+
+/// `tints` over the components of a world
+pub fn tints_world<G, T>(world: &World, dt: f32)
+where
+    G: SpriteComponent + 'static,
+    T: Tweened<Value = [f32; 4]> + 'static,
+{
+    tints(&mut world.borrow_mut::<G>(), &mut world.borrow_mut::<T>(), dt);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::FixedSizeCamera;
+
+    const EASINGS: [Easing; 16] = [
+        Easing::Linear,
+        Easing::QuadIn, Easing::QuadOut, Easing::QuadInOut,
+        Easing::CubicIn, Easing::CubicOut, Easing::CubicInOut,
+        Easing::ElasticIn, Easing::ElasticOut, Easing::ElasticInOut,
+        Easing::BackIn, Easing::BackOut, Easing::BackInOut,
+        Easing::BounceIn, Easing::BounceOut, Easing::BounceInOut,
+    ];
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 0.0001
+    }
+
+    #[test]
+    fn easings_start_at_zero_and_end_at_one() {
+        for easing in EASINGS {
+            assert!(close(easing.apply(0.0), 0.0), "{:?} starts at {}", easing, easing.apply(0.0));
+            assert!(close(easing.apply(1.0), 1.0), "{:?} ends at {}", easing, easing.apply(1.0));
+
+            // Progress out of range is clamped
+            assert!(close(easing.apply(-1.0), 0.0));
+            assert!(close(easing.apply(2.0), 1.0));
+        }
+    }
+
+    #[test]
+    fn in_out_easings_are_halfway_in_the_middle() {
+        for easing in [Easing::Linear, Easing::QuadInOut, Easing::CubicInOut, Easing::ElasticInOut, Easing::BackInOut, Easing::BounceInOut] {
+            assert!(close(easing.apply(0.5), 0.5), "{:?} is at {}", easing, easing.apply(0.5));
+        }
+    }
+
+    #[test]
+    fn easings_have_their_shape() {
+        assert!(close(Easing::QuadIn.apply(0.5), 0.25));
+        assert!(close(Easing::QuadOut.apply(0.5), 0.75));
+        assert!(close(Easing::CubicIn.apply(0.5), 0.125));
+        assert!(close(Easing::CubicOut.apply(0.5), 0.875));
+
+        // In and out curves mirror each other
+        for (ease_in, ease_out) in [
+            (Easing::QuadIn, Easing::QuadOut),
+            (Easing::CubicIn, Easing::CubicOut),
+            (Easing::ElasticIn, Easing::ElasticOut),
+            (Easing::BackIn, Easing::BackOut),
+            (Easing::BounceIn, Easing::BounceOut),
+        ] {
+            for k in 0..=10 {
+                let t = k as f32 / 10.0;
+                assert!(close(ease_in.apply(t), 1.0 - ease_out.apply(1.0 - t)), "{:?} at {}", ease_in, t);
+            }
+        }
+
+        // Back dips below the start, elastic overshoots the end
+        assert!(Easing::BackIn.apply(0.2) < 0.0);
+        assert!((0..100).any(|k| Easing::ElasticOut.apply(k as f32 / 100.0) > 1.0));
+
+        // Bounces touch the ground between hops without going below it
+        assert!((0..=100).all(|k| Easing::BounceOut.apply(k as f32 / 100.0) >= 0.0));
+        assert!(close(Easing::BounceOut.apply(1.0 / 2.75), 1.0));
+    }
+
+    #[test]
+    fn tween_finishes_with_the_time_left_over() {
+        let mut tween = Tween::new(0.0, 10.0, 1.0, Easing::Linear);
+
+        assert_eq!(tween.advance(0.5), 0.0);
+        assert!(close(tween.value(), 5.0));
+        assert!(!tween.finished());
+
+        assert!(close(tween.advance(0.75), 0.25));
+        assert!(tween.finished());
+        assert_eq!(tween.value(), 10.0);
+
+        // Finished tweens hand back all of the time
+        assert_eq!(tween.advance(1.0), 1.0);
+
+        tween.restart();
+        assert!(!tween.finished());
+        assert_eq!(tween.value(), 0.0);
+    }
+
+    #[test]
+    fn tween_waits_repeats_and_yoyos() {
+        let mut tween = Tween::new(0.0, 10.0, 1.0, Easing::Linear).delay(0.5).repeat(2).yoyo(true);
+
+        tween.advance(0.5);
+        assert_eq!(tween.value(), 0.0);
+
+        tween.advance(1.25);
+        assert!(close(tween.value(), 7.5), "{}", tween.value());
+
+        tween.advance(1.0);
+        assert!(close(tween.value(), 2.5), "{}", tween.value());
+        assert!(!tween.finished());
+
+        assert!(close(tween.advance(1.0), 0.25));
+        assert!(tween.finished());
+        assert_eq!(tween.value(), 10.0);
+
+        let mut forever = Tween::new(0.0, 1.0, 1.0, Easing::Linear).forever();
+        assert_eq!(forever.advance(100.5), 0.0);
+        assert!(!forever.finished());
+    }
+
+    #[test]
+    fn sequences_and_tuples_finish_with_their_last_tween() {
+        let mut path = Sequence::new(vec![
+            Tween::new((0.0, 0.0), (10.0, 0.0), 1.0, Easing::Linear),
+            Tween::new((10.0, 0.0), (10.0, 10.0), 1.0, Easing::Linear),
+        ]);
+
+        path.advance(1.5);
+        assert_eq!(path.current(), 1);
+        assert_eq!(path.value(), (10.0, 5.0));
+
+        assert!(close(path.advance(1.0), 0.5));
+        assert!(path.finished());
+
+        let mut both = (
+            Tween::new(0.0, 1.0, 1.0, Easing::Linear),
+            Tween::new(0.0, 1.0, 2.0, Easing::Linear),
+        );
+        assert!(close(both.advance(1.5), 0.0));
+        assert!(!both.finished());
+        assert!(close(both.advance(1.0), 0.5));
+        assert!(both.finished());
+    }
+
+    #[test]
+    fn camera_tweens_stay_in_bounds() {
+        let mut cam = FixedSizeCamera::new(Rect::new(0.0, 0.0, 100.0, 50.0), Rect::new(40, 20, 20, 10), 1.0, (400, 200), 1.0);
+        cam.set_bounds(Some(Rect::new(0.0, 0.0, 300.0, 50.0)));
+
+        let mut pan = Tween::new((0.0, 0.0), (400.0, 20.0), 1.0, Easing::Linear);
+        camera(&mut cam, &mut pan, 0.5);
+        assert_eq!((cam.rect().x, cam.rect().y), (200.0, 0.0));
+
+        camera(&mut cam, &mut pan, 0.5);
+        assert_eq!((cam.rect().x, cam.rect().y), (200.0, 0.0));
+        assert!(pan.finished());
+
+        let mut zoom = Tween::new((100.0, 50.0), (50.0, 25.0), 1.0, Easing::Linear);
+        camera_size(&mut cam, &mut zoom, 1.0);
+        assert_eq!((cam.rect().w, cam.rect().h), (50.0, 25.0));
+        assert_eq!(cam.viewport().1, [400, 200]);
+    }
+}
@@ -0,0 +1,199 @@
+use std::any::{type_name, Any, TypeId};
+use std::cell::{Ref, RefCell, RefMut};
+use std::collections::HashMap;
+
+/// Id of an entity in a `World`. The generation changes whenever an index is reused, so ids of
+/// despawned entities never refer to whatever took their place.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Entity {
+    index: u32,
+    generation: u32,
+}
+
+impl Entity {
+    /// Index of the entity in every component `Vec`
+    pub fn index(&self) -> usize {
+        self.index as usize
+    }
+
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+/// Component storage with its type erased
+trait Storage {
+    fn resize(&mut self, len: usize);
+    fn remove(&mut self, index: usize);
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: 'static> Storage for RefCell<Vec<Option<T>>> {
+    fn resize(&mut self, len: usize) {
+        self.get_mut().resize_with(len, || None);
+    }
+
+    fn remove(&mut self, index: usize) {
+        self.get_mut()[index] = None;
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Entities and their components. Every component type is kept in a `Vec<Option<T>>` indexed
+/// by `Entity::index`, the same layout the systems work on, and all of them have the same
+/// length. Storages can be borrowed separately so several can be handed to a system at once,
+/// which is why the `*_world` adapters of the systems need a different type for each of their
+/// parameters: the same type twice is borrowed twice and panics.
+pub struct World {
+    generations: Vec<u32>,
+    alive: Vec<bool>,
+    living: usize,
+    free: Vec<u32>,
+    storages: HashMap<TypeId, Box<dyn Storage>>,
+}
+
+impl Default for World {
+    fn default() -> World {
+        World::new()
+    }
+}
+
+impl World {
+    pub fn new() -> World {
+        World {
+            generations: Vec::new(),
+            alive: Vec::new(),
+            living: 0,
+            free: Vec::new(),
+            storages: HashMap::new(),
+        }
+    }
+
+    /// Number of living entities
+    pub fn len(&self) -> usize {
+        self.living
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.living == 0
+    }
+
+    /// Number of indices in use or free, which is the length of every storage
+    pub fn slots(&self) -> usize {
+        self.generations.len()
+    }
+
+    pub fn spawn(&mut self) -> Entity {
+        self.living += 1;
+
+        if let Some(index) = self.free.pop() {
+            self.alive[index as usize] = true;
+            return Entity { index, generation: self.generations[index as usize] };
+        }
+
+        let index = self.generations.len() as u32;
+        self.generations.push(0);
+        self.alive.push(true);
+
+        let len = self.slots();
+        for storage in self.storages.values_mut() {
+            storage.resize(len);
+        }
+
+        Entity { index, generation: 0 }
+    }
+
+    /// Remove an entity and all of its components. Returns false if it was already gone.
+    pub fn despawn(&mut self, entity: Entity) -> bool {
+        if !self.is_alive(entity) { return false; }
+
+        for storage in self.storages.values_mut() {
+            storage.remove(entity.index());
+        }
+
+        self.alive[entity.index()] = false;
+        self.living -= 1;
+        self.generations[entity.index()] += 1;
+        self.free.push(entity.index);
+
+        true
+    }
+
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        self.generations.get(entity.index()) == Some(&entity.generation) && self.alive[entity.index()]
+    }
+
+    /// Id of the entity living at an index, for turning indices reported by systems back into
+    /// entities
+    pub fn entity(&self, index: usize) -> Option<Entity> {
+        if !*self.alive.get(index)? { return None; }
+
+        Some(Entity { index: index as u32, generation: self.generations[index] })
+    }
+
+    /// Every living entity, in index order
+    pub fn entities(&self) -> impl Iterator<Item = Entity> + '_ {
+        (0..self.slots()).filter_map(move |i| self.entity(i))
+    }
+
+    /// Create the storage for a component type, needed before borrowing a type that was never
+    /// inserted
+    pub fn register<T: 'static>(&mut self) {
+        let len = self.slots();
+        self.storages.entry(TypeId::of::<T>()).or_insert_with(|| {
+            let mut storage = RefCell::new(Vec::<Option<T>>::new());
+            storage.resize(len);
+            Box::new(storage)
+        });
+    }
+
+    /// Add a component to an entity, returning the one it replaced
+    pub fn insert<T: 'static>(&mut self, entity: Entity, component: T) -> Option<T> {
+        assert!(self.is_alive(entity), "Inserting a component for a despawned entity");
+
+        self.register::<T>();
+        self.borrow_mut::<T>()[entity.index()].replace(component)
+    }
+
+    pub fn remove<T: 'static>(&mut self, entity: Entity) -> Option<T> {
+        if !self.is_alive(entity) || !self.storages.contains_key(&TypeId::of::<T>()) { return None; }
+
+        self.borrow_mut::<T>()[entity.index()].take()
+    }
+
+    pub fn get<T: 'static>(&self, entity: Entity) -> Option<Ref<'_, T>> {
+        if !self.is_alive(entity) { return None; }
+
+        let storage = self.storage::<T>()?.borrow();
+        Ref::filter_map(storage, |s| s[entity.index()].as_ref()).ok()
+    }
+
+    pub fn get_mut<T: 'static>(&self, entity: Entity) -> Option<RefMut<'_, T>> {
+        if !self.is_alive(entity) { return None; }
+
+        let storage = self.storage::<T>()?.borrow_mut();
+        RefMut::filter_map(storage, |s| s[entity.index()].as_mut()).ok()
+    }
+
+    /// All components of a type, panics if the type was never registered or is already
+    /// borrowed mutably
+    pub fn borrow<T: 'static>(&self) -> Ref<'_, Vec<Option<T>>> {
+        self.storage::<T>().expect("Component type not registered").try_borrow()
+            .unwrap_or_else(|_| panic!("{} is already borrowed mutably, is it passed as two different components?", type_name::<T>()))
+    }
+
+    /// All components of a type, panics if the type was never registered or is already
+    /// borrowed
+    pub fn borrow_mut<T: 'static>(&self) -> RefMut<'_, Vec<Option<T>>> {
+        self.storage::<T>().expect("Component type not registered").try_borrow_mut()
+            .unwrap_or_else(|_| panic!("{} is already borrowed, is it passed as two different components?", type_name::<T>()))
+    }
+
+    fn storage<T: 'static>(&self) -> Option<&RefCell<Vec<Option<T>>>> {
+        self.storages.get(&TypeId::of::<T>())
+            .map(|s| s.as_any().downcast_ref::<RefCell<Vec<Option<T>>>>().unwrap())
+    }
+}
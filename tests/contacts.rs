@@ -0,0 +1,66 @@
+use systems::Rect;
+use systems::component::{PositionComponent, VelocityComponent, PhysicsComponent, BodyComponent};
+use systems::physics::{PhysicsSystem, BodyType, Timestep, ContactState, Dummy};
+
+struct Position { x: f32, y: f32 }
+impl PositionComponent for Position {
+    fn x(&self) -> f32 { self.x }
+    fn y(&self) -> f32 { self.y }
+    fn set_x(&mut self, x: f32) { self.x = x }
+    fn set_y(&mut self, y: f32) { self.y = y }
+}
+
+struct Velocity { x: f32, y: f32 }
+impl VelocityComponent for Velocity {
+    fn x(&self) -> f32 { self.x }
+    fn y(&self) -> f32 { self.y }
+    fn set_x(&mut self, x: f32) { self.x = x }
+    fn set_y(&mut self, y: f32) { self.y = y }
+}
+
+struct Physics { hitbox: Rect<f32>, body_type: BodyType }
+impl PhysicsComponent for Physics {
+    fn hitbox(&self) -> Rect<f32> { self.hitbox }
+    fn set_x_collision(&mut self, _: Option<Rect<f32>>) {}
+    fn set_y_collision(&mut self, _: Option<Rect<f32>>) {}
+    fn body_type(&self) -> BodyType { self.body_type }
+}
+
+#[derive(Default)]
+struct Body { on_ground: bool, on_wall: bool }
+impl BodyComponent for Body {
+    fn on_ground(&self) -> bool { self.on_ground }
+    fn on_wall(&self) -> bool { self.on_wall }
+    fn set_on_ground(&mut self, on_ground: bool) { self.on_ground = on_ground }
+    fn set_on_wall(&mut self, on_wall: bool) { self.on_wall = on_wall }
+}
+
+#[test]
+fn only_the_closest_wall_in_a_sweep_is_touched() {
+    let mut sys = PhysicsSystem::new();
+    sys.set_timestep(Timestep::Fixed(1.0 / 60.0));
+
+    // Moving 10px this tick, far enough to cross both walls
+    let mut p = vec![
+        Some(Position { x: 0.0, y: 0.0 }),
+        Some(Position { x: 12.0, y: 0.0 }),
+        Some(Position { x: 15.0, y: 0.0 }),
+    ];
+    let mut v = vec![Some(Velocity { x: 600.0, y: 0.0 }), None, None];
+    let mut ph = vec![
+        Some(Physics { hitbox: Rect::new(0.0, 0.0, 8.0, 8.0), body_type: BodyType::Dynamic }),
+        Some(Physics { hitbox: Rect::new(0.0, 0.0, 2.0, 8.0), body_type: BodyType::Static }),
+        Some(Physics { hitbox: Rect::new(0.0, 0.0, 2.0, 8.0), body_type: BodyType::Static }),
+    ];
+    let mut b = vec![Some(Body::default()), None, None];
+
+    for tick in 0..3 {
+        sys.simulate(&mut p, &mut v, &mut ph, &mut b, None::<&Dummy>);
+
+        let pairs: Vec<_> = sys.events().iter().map(|e| (e.a, e.b, e.state)).collect();
+        let state = if tick == 0 { ContactState::Started } else { ContactState::Persisting };
+        assert_eq!(pairs, vec![(0, 1, state)], "events after tick {}", tick);
+    }
+
+    assert!((p[0].as_ref().unwrap().x - 4.0).abs() < 0.001);
+}
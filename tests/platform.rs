@@ -0,0 +1,152 @@
+use systems::{Rect, Fixed};
+use systems::component::{PositionComponent, VelocityComponent, PhysicsComponent, BodyComponent};
+use systems::physics::{PhysicsSystem, BodyType, Timestep, Dummy};
+
+struct Position { x: f32, y: f32 }
+impl PositionComponent for Position {
+    fn x(&self) -> f32 { self.x }
+    fn y(&self) -> f32 { self.y }
+    fn set_x(&mut self, x: f32) { self.x = x }
+    fn set_y(&mut self, y: f32) { self.y = y }
+}
+
+struct Velocity { x: f32, y: f32 }
+impl VelocityComponent for Velocity {
+    fn x(&self) -> f32 { self.x }
+    fn y(&self) -> f32 { self.y }
+    fn set_x(&mut self, x: f32) { self.x = x }
+    fn set_y(&mut self, y: f32) { self.y = y }
+}
+
+struct Physics { hitbox: Rect<f32>, body_type: BodyType }
+impl PhysicsComponent for Physics {
+    fn hitbox(&self) -> Rect<f32> { self.hitbox }
+    fn set_x_collision(&mut self, _: Option<Rect<f32>>) {}
+    fn set_y_collision(&mut self, _: Option<Rect<f32>>) {}
+    fn body_type(&self) -> BodyType { self.body_type }
+}
+
+#[derive(Default)]
+struct Body { on_ground: bool, on_wall: bool }
+impl BodyComponent for Body {
+    fn on_ground(&self) -> bool { self.on_ground }
+    fn on_wall(&self) -> bool { self.on_wall }
+    fn set_on_ground(&mut self, on_ground: bool) { self.on_ground = on_ground }
+    fn set_on_wall(&mut self, on_wall: bool) { self.on_wall = on_wall }
+}
+
+#[test]
+fn rider_is_carried_by_horizontal_platform() {
+    let mut sys = PhysicsSystem::new();
+    sys.set_gravity(0.0, 500.0);
+    sys.set_timestep(Timestep::Fixed(1.0 / 60.0));
+
+    // Platform moving right with a rider standing on it and a wall it never touches
+    let mut p = vec![
+        Some(Position { x: 0.0, y: 100.0 }),
+        Some(Position { x: 10.0, y: 84.0 }),
+        Some(Position { x: 0.0, y: 200.0 }),
+    ];
+    let mut v = vec![
+        Some(Velocity { x: 100.0, y: 0.0 }),
+        Some(Velocity { x: 0.0, y: 0.0 }),
+        Some(Velocity { x: 50.0, y: 0.0 }),
+    ];
+    let mut ph = vec![
+        Some(Physics { hitbox: Rect::new(0.0, 0.0, 48.0, 8.0), body_type: BodyType::Kinematic }),
+        Some(Physics { hitbox: Rect::new(0.0, 0.0, 16.0, 16.0), body_type: BodyType::Dynamic }),
+        Some(Physics { hitbox: Rect::new(0.0, 0.0, 16.0, 16.0), body_type: BodyType::Static }),
+    ];
+    let mut b = vec![None, Some(Body::default()), None];
+
+    for _ in 0..20 {
+        sys.simulate(&mut p, &mut v, &mut ph, &mut b, None::<&Dummy>);
+    }
+
+    let platform = p[0].as_ref().unwrap();
+    let rider = p[1].as_ref().unwrap();
+    let wall = p[2].as_ref().unwrap();
+
+    assert!((platform.x - 100.0 * 20.0 / 60.0).abs() < 0.001);
+    assert!((rider.x - platform.x - 10.0).abs() < 0.001);
+    assert!((rider.y - 84.0).abs() < 0.001);
+    assert!(b[1].as_ref().unwrap().on_ground);
+
+    // Static bodies ignore their velocity
+    assert_eq!(wall.x, 0.0);
+}
+
+#[test]
+fn platform_pushes_bodies_in_its_way() {
+    let mut sys = PhysicsSystem::new();
+    sys.set_timestep(Timestep::Fixed(1.0 / 60.0));
+
+    let mut p = vec![
+        Some(Position { x: 0.0, y: 0.0 }),
+        Some(Position { x: 50.0, y: 0.0 }),
+    ];
+    let mut v = vec![
+        Some(Velocity { x: 400.0, y: 0.0 }),
+        Some(Velocity { x: 0.0, y: 0.0 }),
+    ];
+    let mut ph = vec![
+        Some(Physics { hitbox: Rect::new(0.0, 0.0, 48.0, 8.0), body_type: BodyType::Kinematic }),
+        Some(Physics { hitbox: Rect::new(0.0, 0.0, 8.0, 8.0), body_type: BodyType::Dynamic }),
+    ];
+    let mut b: Vec<Option<Body>> = vec![None, None];
+
+    for _ in 0..20 {
+        sys.simulate(&mut p, &mut v, &mut ph, &mut b, None::<&Dummy>);
+    }
+
+    let platform = p[0].as_ref().unwrap();
+    let pushed = p[1].as_ref().unwrap();
+
+    assert!((platform.x - 400.0 * 20.0 / 60.0).abs() < 0.001);
+    assert!(pushed.x >= platform.x + 48.0 - 0.001);
+}
+
+#[test]
+fn velocity_leaves_static_bodies_in_place() {
+    let mut sys = PhysicsSystem::new();
+    sys.set_timestep(Timestep::Fixed(0.5));
+
+    let mut p = vec![
+        Some(Position { x: 0.0, y: 0.0 }),
+        Some(Position { x: 0.0, y: 0.0 }),
+    ];
+    let mut v = vec![
+        Some(Velocity { x: 10.0, y: 0.0 }),
+        Some(Velocity { x: 10.0, y: 0.0 }),
+    ];
+    let ph = vec![
+        Some(Physics { hitbox: Rect::new(0.0, 0.0, 8.0, 8.0), body_type: BodyType::Static }),
+        None,
+    ];
+
+    sys.velocity_bodies(&mut p, &mut v, &ph);
+
+    assert_eq!(p[0].as_ref().unwrap().x, 0.0);
+    assert_eq!(p[1].as_ref().unwrap().x, 5.0);
+
+    // The plain version knows nothing of body types
+    sys.velocity(&mut p, &v);
+
+    assert_eq!(p[0].as_ref().unwrap().x, 5.0);
+}
+
+#[test]
+fn velocity_bodies_snaps_velocities() {
+    let mut sys = PhysicsSystem::new();
+    sys.set_deterministic(1.0 / 60.0);
+
+    let mut p = vec![Some(Position { x: 0.0, y: 0.0 })];
+    let mut v = vec![Some(Velocity { x: 1.0 / 3.0, y: 0.0 })];
+    let ph: Vec<Option<Physics>> = vec![None];
+
+    sys.velocity_bodies(&mut p, &mut v, &ph);
+
+    let vx = v[0].as_ref().unwrap().x;
+    assert_ne!(vx, 1.0 / 3.0);
+    assert_eq!(vx, Fixed::snap(vx));
+}
@@ -0,0 +1,160 @@
+use systems::Rect;
+use systems::component::{PositionComponent, VelocityComponent, PhysicsComponent};
+use systems::physics::{PhysicsSystem, CollisionMap, TileKind, Timestep, Dummy};
+
+struct Position { x: f32, y: f32 }
+impl PositionComponent for Position {
+    fn x(&self) -> f32 { self.x }
+    fn y(&self) -> f32 { self.y }
+    fn set_x(&mut self, x: f32) { self.x = x }
+    fn set_y(&mut self, y: f32) { self.y = y }
+}
+
+struct Velocity { x: f32, y: f32 }
+impl VelocityComponent for Velocity {
+    fn x(&self) -> f32 { self.x }
+    fn y(&self) -> f32 { self.y }
+    fn set_x(&mut self, x: f32) { self.x = x }
+    fn set_y(&mut self, y: f32) { self.y = y }
+}
+
+struct Physics { hitbox: Rect<f32>, category: u32, sensor: bool }
+impl PhysicsComponent for Physics {
+    fn hitbox(&self) -> Rect<f32> { self.hitbox }
+    fn set_x_collision(&mut self, _: Option<Rect<f32>>) {}
+    fn set_y_collision(&mut self, _: Option<Rect<f32>>) {}
+    fn category(&self) -> u32 { self.category }
+    fn is_sensor(&self) -> bool { self.sensor }
+}
+
+/// Row of 16px tiles with a solid wall at the end
+struct Corridor;
+impl CollisionMap for Corridor {
+    fn test(&self, _: &Rect<f32>) -> bool { false }
+    fn tile_size(&self) -> Option<f32> { Some(16.0) }
+    fn tile(&self, x: i32, y: i32) -> TileKind {
+        if y == 0 && x == 5 { TileKind::Solid } else { TileKind::Empty }
+    }
+    fn bounds(&self) -> Option<Rect<f32>> { Some(Rect::new(0.0, 0.0, 96.0, 16.0)) }
+}
+
+/// Map without tiles or bounds, solid right of x = 300
+struct Wall;
+impl CollisionMap for Wall {
+    fn test(&self, rect: &Rect<f32>) -> bool { rect.x + rect.w > 300.0 }
+}
+
+fn boxed(x: f32, y: f32, size: f32, category: u32, sensor: bool) -> (Option<Position>, Option<Physics>) {
+    (Some(Position { x, y }), Some(Physics { hitbox: Rect::new(0.0, 0.0, size, size), category, sensor }))
+}
+
+/// Scene of a sensor and two boxes in a row, plus a box in another category below them.
+/// Ticked once so the broad phase knows where everything is.
+fn scene() -> (PhysicsSystem, Vec<Option<Position>>, Vec<Option<Physics>>) {
+    let (mut p, mut ph): (Vec<_>, Vec<_>) = [
+        boxed(100.0, 0.0, 10.0, 1, false),
+        boxed(200.0, 0.0, 10.0, 1, false),
+        boxed(50.0, 0.0, 10.0, 1, true),
+        boxed(100.0, 100.0, 20.0, 2, false),
+    ].into_iter().unzip();
+    let mut v: Vec<Option<Velocity>> = (0..p.len()).map(|_| None).collect();
+
+    let mut sys = PhysicsSystem::new();
+    sys.set_timestep(Timestep::Fixed(1.0 / 60.0));
+    sys.collision(&mut p, &mut v, &mut ph);
+
+    (sys, p, ph)
+}
+
+#[test]
+fn raycast_hits_closest_entity() {
+    let (sys, p, ph) = scene();
+
+    // The sensor in front is skipped
+    let hit = sys.raycast(&p, &ph, None::<&Dummy>, (0.0, 5.0), (1.0, 0.0), 1000.0, u32::MAX).unwrap();
+    assert_eq!(hit.entity, Some(0));
+    assert_eq!(hit.point, (100.0, 5.0));
+    assert_eq!(hit.normal, (-1.0, 0.0));
+    assert_eq!(hit.distance, 100.0);
+
+    assert!(sys.raycast(&p, &ph, None::<&Dummy>, (0.0, 5.0), (1.0, 0.0), 50.0, u32::MAX).is_none());
+    assert!(sys.raycast(&p, &ph, None::<&Dummy>, (0.0, 5.0), (0.0, 0.0), 1000.0, u32::MAX).is_none());
+
+    // Only categories in the mask are hit
+    assert!(sys.raycast(&p, &ph, None::<&Dummy>, (0.0, 110.0), (1.0, 0.0), 1000.0, 1).is_none());
+    let hit = sys.raycast(&p, &ph, None::<&Dummy>, (0.0, 110.0), (1.0, 0.0), 1000.0, 2).unwrap();
+    assert_eq!(hit.entity, Some(3));
+
+    // Starting inside an entity looks past it
+    let hit = sys.segment(&p, &ph, None::<&Dummy>, (105.0, 5.0), (300.0, 5.0), u32::MAX).unwrap();
+    assert_eq!(hit.entity, Some(1));
+}
+
+#[test]
+fn raycast_without_length_limit() {
+    let (sys, p, ph) = scene();
+
+    let hit = sys.raycast(&p, &ph, None::<&Dummy>, (0.0, 5.0), (1.0, 0.0), f32::INFINITY, u32::MAX).unwrap();
+    assert_eq!(hit.entity, Some(0));
+
+    let hit = sys.raycast(&p, &ph, Some(&Wall), (0.0, 50.0), (1.0, 0.0), f32::INFINITY, u32::MAX).unwrap();
+    assert_eq!(hit.entity, None);
+    assert!((hit.distance - 300.0).abs() < 0.01, "{:?}", hit);
+    assert_eq!(hit.normal, (-1.0, 0.0));
+
+    let hit = sys.box_cast(&p, &ph, None::<&Dummy>, &Rect::new(0.0, 0.0, 4.0, 4.0), (1.0, 0.0), f32::INFINITY, u32::MAX);
+    assert_eq!(hit.unwrap().entity, Some(0));
+
+    // Nothing to hit, the ray gives up instead of marching forever
+    assert!(sys.raycast(&p, &ph, Some(&Wall), (0.0, 50.0), (-1.0, 0.0), f32::INFINITY, u32::MAX).is_none());
+}
+
+#[test]
+fn raycast_against_tiles() {
+    let sys = PhysicsSystem::new();
+    let (p, ph): (Vec<Option<Position>>, Vec<Option<Physics>>) = (Vec::new(), Vec::new());
+
+    let hit = sys.raycast(&p, &ph, Some(&Corridor), (8.0, 8.0), (1.0, 0.0), f32::INFINITY, u32::MAX).unwrap();
+    assert_eq!(hit.entity, None);
+    assert_eq!(hit.point, (80.0, 8.0));
+    assert_eq!(hit.normal, (-1.0, 0.0));
+
+    // Leaving the map, or starting outside and moving away, hits nothing
+    assert!(sys.raycast(&p, &ph, Some(&Corridor), (8.0, 8.0), (-1.0, 0.0), f32::INFINITY, u32::MAX).is_none());
+    assert!(sys.raycast(&p, &ph, Some(&Corridor), (8.0, 40.0), (0.0, 1.0), f32::INFINITY, u32::MAX).is_none());
+}
+
+#[test]
+fn box_cast_stops_where_rect_touches() {
+    let (sys, p, ph) = scene();
+
+    let hit = sys.box_cast(&p, &ph, None::<&Dummy>, &Rect::new(0.0, 0.0, 4.0, 4.0), (1.0, 0.0), 1000.0, u32::MAX).unwrap();
+    assert_eq!(hit.entity, Some(0));
+    assert_eq!(hit.point, (96.0, 0.0));
+    assert_eq!(hit.distance, 96.0);
+
+    // Passes just under the boxes in a row
+    assert!(sys.box_cast(&p, &ph, None::<&Dummy>, &Rect::new(0.0, 11.0, 4.0, 4.0), (1.0, 0.0), 1000.0, 1).is_none());
+
+    let hit = sys.box_cast(&p, &ph, Some(&Corridor), &Rect::new(0.0, 4.0, 8.0, 8.0), (1.0, 0.0), 1000.0, u32::MAX).unwrap();
+    assert_eq!(hit.entity, None);
+    assert_eq!(hit.point, (72.0, 4.0));
+}
+
+#[test]
+fn point_and_rect_queries() {
+    let (sys, p, ph) = scene();
+
+    assert_eq!(sys.query_point(&p, &ph, 105.0, 5.0, u32::MAX), vec![0]);
+    assert_eq!(sys.query_point(&p, &ph, 55.0, 5.0, u32::MAX), vec![2]);
+    assert!(sys.query_point(&p, &ph, 150.0, 5.0, u32::MAX).is_empty());
+    assert!(sys.query_point(&p, &ph, 110.0, 110.0, 1).is_empty());
+
+    let mut found = sys.query_rect(&p, &ph, &Rect::new(90.0, 0.0, 120.0, 10.0), u32::MAX);
+    found.sort();
+    assert_eq!(found, vec![0, 1]);
+
+    let mut found = sys.query_rect(&p, &ph, &Rect::new(0.0, 0.0, 300.0, 200.0), 2);
+    found.sort();
+    assert_eq!(found, vec![3]);
+}
@@ -0,0 +1,198 @@
+use systems::Rect;
+use systems::component::{PositionComponent, VelocityComponent, PhysicsComponent, BodyComponent};
+use systems::physics::{PhysicsSystem, CollisionMap, TileKind, Timestep};
+
+struct Position { x: f32, y: f32 }
+impl PositionComponent for Position {
+    fn x(&self) -> f32 { self.x }
+    fn y(&self) -> f32 { self.y }
+    fn set_x(&mut self, x: f32) { self.x = x }
+    fn set_y(&mut self, y: f32) { self.y = y }
+}
+
+struct Velocity { x: f32, y: f32 }
+impl VelocityComponent for Velocity {
+    fn x(&self) -> f32 { self.x }
+    fn y(&self) -> f32 { self.y }
+    fn set_x(&mut self, x: f32) { self.x = x }
+    fn set_y(&mut self, y: f32) { self.y = y }
+}
+
+struct Physics { hitbox: Rect<f32> }
+impl PhysicsComponent for Physics {
+    fn hitbox(&self) -> Rect<f32> { self.hitbox }
+    fn set_x_collision(&mut self, _: Option<Rect<f32>>) {}
+    fn set_y_collision(&mut self, _: Option<Rect<f32>>) {}
+}
+
+#[derive(Default)]
+struct Body { on_ground: bool, on_wall: bool }
+impl BodyComponent for Body {
+    fn on_ground(&self) -> bool { self.on_ground }
+    fn on_wall(&self) -> bool { self.on_wall }
+    fn set_on_ground(&mut self, on_ground: bool) { self.on_ground = on_ground }
+    fn set_on_wall(&mut self, on_wall: bool) { self.on_wall = on_wall }
+}
+
+const TILE: f32 = 16.0;
+
+/// Map drawn with one character per 16px tile: `#` solid, `-` one way, `/` and `\` 45°
+/// slopes, `l` and `h` the low and high tiles of a 22.5° slope rising to the right
+struct Level { rows: Vec<Vec<TileKind>> }
+
+impl Level {
+    fn new(rows: &[&str]) -> Level {
+        let rows = rows.iter().map(|row| row.chars().map(|c| match c {
+            '#' => TileKind::Solid,
+            '-' => TileKind::OneWayUp,
+            '/' => TileKind::SlopeRight,
+            '\\' => TileKind::SlopeLeft,
+            'l' => TileKind::SlopeRightLow,
+            'h' => TileKind::SlopeRightHigh,
+            _ => TileKind::Empty,
+        }).collect()).collect();
+
+        Level { rows }
+    }
+}
+
+impl CollisionMap for Level {
+    fn test(&self, _: &Rect<f32>) -> bool { false }
+    fn tile_size(&self) -> Option<f32> { Some(TILE) }
+    fn tile(&self, x: i32, y: i32) -> TileKind {
+        if x < 0 || y < 0 { return TileKind::Empty; }
+        self.rows.get(y as usize).and_then(|row| row.get(x as usize)).copied().unwrap_or(TileKind::Empty)
+    }
+}
+
+/// State of the walker after a tick, its foot (bottom center) and whether it stands
+struct Step { foot: (f32, f32), on_ground: bool, on_wall: bool }
+
+/// Run an 8x8 body through a level with gravity, walking at `walk` px/s. A `jump` is applied
+/// on the first tick.
+fn run(level: &Level, start: (f32, f32), walk: f32, jump: f32, ticks: usize) -> Vec<Step> {
+    let mut sys = PhysicsSystem::new();
+    sys.set_gravity(0.0, 600.0);
+    sys.set_timestep(Timestep::Fixed(1.0 / 60.0));
+
+    let mut p = vec![Some(Position { x: start.0, y: start.1 })];
+    let mut v = vec![Some(Velocity { x: walk, y: jump })];
+    let mut ph = vec![Some(Physics { hitbox: Rect::new(0.0, 0.0, 8.0, 8.0) })];
+    let mut b = vec![Some(Body::default())];
+
+    (0..ticks).map(|_| {
+        v[0].as_mut().unwrap().x = walk;
+        sys.simulate(&mut p, &mut v, &mut ph, &mut b, Some(level));
+
+        let pos = p[0].as_ref().unwrap();
+        let body = b[0].as_ref().unwrap();
+        Step { foot: (pos.x + 4.0, pos.y + 8.0), on_ground: body.on_ground, on_wall: body.on_wall }
+    }).collect()
+}
+
+/// Check that the walker stayed on the ground the whole way, with its foot on `surface`
+/// wherever that knows the floor
+fn follows(steps: &[Step], surface: impl Fn(f32) -> Option<f32>) {
+    for (tick, step) in steps.iter().enumerate() {
+        assert!(step.on_ground, "in the air after tick {}", tick);
+
+        if let Some(floor) = surface(step.foot.0) {
+            assert!((step.foot.1 - floor).abs() < 0.01,
+                "foot at {:?} after tick {}, floor at {}", step.foot, tick, floor);
+        }
+    }
+}
+
+fn steep() -> Level {
+    Level::new(&[
+        "............",
+        "............",
+        "......../###",
+        "......./####",
+        "....../#####",
+        "############",
+    ])
+}
+
+/// Floor of `steep` under a foot. Within half a body of the ledge at the top the body
+/// already stands on the ledge, so the floor there is left open.
+fn steep_floor(x: f32) -> Option<f32> {
+    if (x - 9.0 * TILE).abs() < 4.0 { return None; }
+    Some((80.0 - (x - 6.0 * TILE)).clamp(32.0, 80.0))
+}
+
+fn gentle() -> Level {
+    Level::new(&[
+        "..............",
+        "..............",
+        "..............",
+        "........lh####",
+        "##############",
+    ])
+}
+
+/// Floor of `gentle` under a foot, open near the ledge like `steep_floor`
+fn gentle_floor(x: f32) -> Option<f32> {
+    if (x - 10.0 * TILE).abs() < 4.0 { return None; }
+    Some((64.0 - (x - 8.0 * TILE) / 2.0).clamp(48.0, 64.0))
+}
+
+#[test]
+fn walks_up_and_down_45_degree_slope() {
+    let up = run(&steep(), (40.0, 72.0), 60.0, 0.0, 120);
+    follows(&up, steep_floor);
+    assert_eq!(up.last().unwrap().foot, (164.0, 32.0));
+
+    let down = run(&steep(), (160.0, 24.0), -60.0, 0.0, 120);
+    follows(&down, steep_floor);
+    assert_eq!(down.last().unwrap().foot, (44.0, 80.0));
+}
+
+#[test]
+fn walks_up_and_down_22_5_degree_slope() {
+    let up = run(&gentle(), (80.0, 56.0), 60.0, 0.0, 120);
+    follows(&up, gentle_floor);
+    assert_eq!(up.last().unwrap().foot, (204.0, 48.0));
+
+    let down = run(&gentle(), (200.0, 40.0), -60.0, 0.0, 120);
+    follows(&down, gentle_floor);
+    assert_eq!(down.last().unwrap().foot, (84.0, 64.0));
+}
+
+#[test]
+fn jumps_up_through_one_way_platform_and_lands_on_it() {
+    let level = Level::new(&[
+        "....",
+        "....",
+        "----",
+        "....",
+        "####",
+    ]);
+
+    let steps = run(&level, (24.0, 56.0), 0.0, -400.0, 120);
+
+    // Rose past the platform without being stopped by it
+    let apex = steps.iter().map(|s| s.foot.1).fold(f32::INFINITY, f32::min);
+    assert!(apex < 32.0 - 8.0, "only got up to {}", apex);
+
+    let last = steps.last().unwrap();
+    assert_eq!(last.foot.1, 32.0);
+    assert!(last.on_ground);
+}
+
+#[test]
+fn stops_against_solid_wall() {
+    let level = Level::new(&[
+        "........",
+        "....#...",
+        "....#...",
+        "########",
+    ]);
+
+    let steps = run(&level, (16.0, 40.0), 60.0, 0.0, 60);
+
+    let last = steps.last().unwrap();
+    assert_eq!(last.foot, (60.0, 48.0));
+    assert!(last.on_ground);
+    assert!(last.on_wall);
+}